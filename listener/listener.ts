@@ -31,6 +31,7 @@ interface BetPlacedEvent {
   optionIndex: number;
   amount: BN;
   totalUserAmount: BN;
+  fee: BN;
 }
 
 interface SettlementProposedEvent {
@@ -53,6 +54,7 @@ interface MarketSettledEvent {
   outcome: number;
   settledBy: PublicKey;
   isAdminResolution: boolean;
+  settleFee: BN;
 }
 
 interface PrizeClaimedEvent {
@@ -61,6 +63,12 @@ interface PrizeClaimedEvent {
   user: PublicKey;
   amount: BN;
   fee: BN;
+  userBetAmount: BN;
+  winningPool: BN;
+  totalPool: BN;
+  settleFee: BN;
+  winnersPool: BN;
+  lastWinner: boolean;
 }
 
 interface BetRefundedEvent {
@@ -81,7 +89,7 @@ interface AdminChangedEvent {
   newAdmin: PublicKey;
 }
 
-const STATUS_NAMES = ['OPEN', 'CLOSED', 'PROPOSED', 'DISPUTED', 'SETTLED', 'CANCELLED'];
+const STATUS_NAMES = ['OPEN', 'CLOSED', 'PROPOSED', 'DISPUTED', 'SETTLED', 'CANCELLED', 'FORFEITED'];
 
 function formatLamports(lamports: BN): string {
   return `${lamports.toNumber() / 1e9} SOL`;
@@ -265,6 +273,11 @@ class SocialBetListener {
     console.log(`👤 User: ${event.user.toString()}`);
     console.log(`💵 Amount: ${formatLamports(event.amount)}`);
     console.log(`📊 Fee: ${formatLamports(event.fee)}`);
+    if (event.lastWinner) {
+      console.log(`🧮 Share: remainder of ${formatLamports(event.winnersPool)} (last winner)`);
+    } else {
+      console.log(`🧮 Share: ${formatLamports(event.winnersPool)} × ${formatLamports(event.userBetAmount)} / ${formatLamports(event.winningPool)}`);
+    }
     console.log(`🕐 Event Time: ${new Date().toISOString()}`);
     console.log('=========================================\n');
   }
@@ -11,7 +11,7 @@ config();
 const PROGRAM_ID = new PublicKey(process.env.PROGRAM_ID || 'ATvmQTJT6JV9eYvBeyDacN9tGUKA4P5ykmxF9zK49CFr');
 const RPC_ENDPOINT = process.env.RPC_ENDPOINT || 'https://api.devnet.solana.com';
 
-const STATUS_NAMES = ['OPEN', 'CLOSED', 'PROPOSED', 'DISPUTED', 'SETTLED', 'CANCELLED'];
+const STATUS_NAMES = ['OPEN', 'CLOSED', 'PROPOSED', 'DISPUTED', 'SETTLED', 'CANCELLED', 'FORFEITED'];
 
 function formatLamports(lamports: BN): string {
   return `${lamports.toNumber() / 1e9} SOL`;
@@ -231,6 +231,8 @@ class HistoricalMarketScanner {
       return {
         admin: config.admin.toString(),
         feeVault: config.feeVault.toString(),
+        treasury: config.treasury.toString(),
+        treasurySplitBps: config.treasurySplitBps,
         createFee: config.createFee.toNumber() / 1e9,
         joinFeeBps: config.joinFeeBps,
         clearingFeeBps: config.clearingFeeBps,
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::DEFAULT_MS_PER_SLOT;
 
 declare_id!("ATvmQTJT6JV9eYvBeyDacN9tGUKA4P5ykmxF9zK49CFr");
 
@@ -8,47 +9,70 @@ pub const MAX_OPTIONS: usize = 10;
 pub const MAX_QUESTION_LEN: usize = 256;
 /// Maximum option label length
 pub const MAX_OPTION_LEN: usize = 64;
+/// Maximum market description length
+pub const MAX_DESCRIPTION_LEN: usize = 200;
+/// Maximum market image URI length
+pub const MAX_IMAGE_URI_LEN: usize = 200;
+/// Layout version written to Config at init
+pub const ACCOUNT_VERSION: u8 = 1;
+/// Layout version written to UserBet at init; `migrate_user_bet` upgrades older bets
+pub const USER_BET_VERSION: u8 = 2;
+/// Layout version written to Market at init; `migrate_market` upgrades older markets
+pub const MARKET_VERSION: u8 = 12;
+/// Config account size: discriminator + admin + fee_vault + create_fee + 3 fee_bps + next_market_id
+/// (the legacy layout), then treasury + treasury_split_bps + min/max stake + claim_window
+/// + max_participants + join_cutoff + min_settle_fee + admin_settle_grace + min/max fee override bps
+/// + 2 flags + max_settle_lag + enforce_user_limits + max_daily_stake + switch_fee_bps
+/// + blacklist_enabled + max_markets_per_creator + cancel_fee_credit + min_distinct_participants
+/// + claim_cooldown + bump + version
+pub const CONFIG_SPACE: usize = 8 + 32 + 32 + 8 + 2 + 2 + 2 + 8 + 32 + 2 + 8 + 8 + 8 + 4 + 8 + 8 + 8 + 2 + 2 + 1 + 1 + 8 + 1 + 8 + 2 + 1 + 4 + 8 + 4 + 8 + 1 + 1;
+/// UserBet account size: discriminator + market_id + user + option_index + amount + claimed + bet_count + version
+/// + join_fee_paid
+pub const USER_BET_SPACE: usize = 8 + 8 + 32 + 1 + 8 + 1 + 4 + 1 + 8;
+/// Length of the UTC day used for daily stake limits
+pub const SECONDS_PER_DAY: i64 = 86_400;
 
 #[program]
 pub mod social_bet {
     use super::*;
 
     /// Initialize the global config (admin settings)
+    ///
+    /// Can only run once: the config PDA is created with `init`, so a second call fails
+    /// because the account is already in use. `admin` must be the signer; hand the role to
+    /// another key afterwards with `set_admin`.
     pub fn initialize_config(
         ctx: Context<InitializeConfig>,
         admin: Pubkey,
-        fee_vault: Pubkey,
-        create_fee: u64,
-        join_fee_bps: u16,
-        clearing_fee_bps: u16,
-        settle_fee_bps: u16,
+        params: ConfigParams,
     ) -> Result<()> {
         let config = &mut ctx.accounts.config;
+        require!(admin != Pubkey::default(), ErrorCode::InvalidAdmin);
+        require_keys_eq!(admin, ctx.accounts.admin.key(), ErrorCode::InvalidAdmin);
+        params.validate(&config.key())?;
+
         config.admin = admin;
-        config.fee_vault = fee_vault;
-        config.create_fee = create_fee;
-        config.join_fee_bps = join_fee_bps;
-        config.clearing_fee_bps = clearing_fee_bps;
-        config.settle_fee_bps = settle_fee_bps;
         config.next_market_id = 1;
+        config.version = ACCOUNT_VERSION;
+        config.bump = ctx.bumps.config;
+        config.apply(params);
+
+        ctx.accounts.outcome_stats.bump = ctx.bumps.outcome_stats;
+        Ok(())
+    }
+
+    /// Create the OutcomeStats account on a deployment initialized before it existed (admin only)
+    ///
+    /// Settlement requires it, so this must run once after upgrading such a deployment.
+    pub fn init_outcome_stats(ctx: Context<InitOutcomeStats>) -> Result<()> {
+        ctx.accounts.outcome_stats.bump = ctx.bumps.outcome_stats;
         Ok(())
     }
 
     /// Update config (admin only)
-    pub fn update_config(
-        ctx: Context<UpdateConfig>,
-        fee_vault: Pubkey,
-        create_fee: u64,
-        join_fee_bps: u16,
-        clearing_fee_bps: u16,
-        settle_fee_bps: u16,
-    ) -> Result<()> {
-        let config = &mut ctx.accounts.config;
-        config.fee_vault = fee_vault;
-        config.create_fee = create_fee;
-        config.join_fee_bps = join_fee_bps;
-        config.clearing_fee_bps = clearing_fee_bps;
-        config.settle_fee_bps = settle_fee_bps;
+    pub fn update_config(ctx: Context<UpdateConfig>, params: ConfigParams) -> Result<()> {
+        params.validate(&ctx.accounts.config.key())?;
+        ctx.accounts.config.apply(params);
         Ok(())
     }
 
@@ -68,15 +92,56 @@ pub mod social_bet {
         Ok(())
     }
 
+    /// Set a new fee vault without touching the fee settings (admin only)
+    pub fn set_fee_vault(ctx: Context<SetFeeVault>, new_fee_vault: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(new_fee_vault != Pubkey::default(), ErrorCode::InvalidFeeVault);
+        require!(new_fee_vault != config.key(), ErrorCode::InvalidFeeVault);
+
+        let old_fee_vault = config.fee_vault;
+        config.fee_vault = new_fee_vault;
+
+        emit!(ConfigFieldUpdated {
+            field: "fee_vault".to_string(),
+            old_value: old_fee_vault,
+            new_value: new_fee_vault,
+        });
+
+        Ok(())
+    }
+
+    /// Block a user from creating markets and placing bets (admin only)
+    ///
+    /// Only enforced while `blacklist_enabled` is set. Claims and refunds stay open so a
+    /// blacklisted user can still exit existing positions.
+    pub fn blacklist_user(ctx: Context<BlacklistUser>, user: Pubkey) -> Result<()> {
+        let entry = &mut ctx.accounts.blacklist_entry;
+        entry.user = user;
+        entry.added_at = Clock::get()?.unix_timestamp;
+
+        emit!(UserBlacklistUpdated { user, blacklisted: true });
+
+        Ok(())
+    }
+
+    /// Lift a blacklist entry and return its rent to the admin (admin only)
+    pub fn unblacklist_user(_ctx: Context<UnblacklistUser>, user: Pubkey) -> Result<()> {
+        emit!(UserBlacklistUpdated { user, blacklisted: false });
+
+        Ok(())
+    }
+
     /// Create a new prediction market
     /// 
     /// # Arguments
     /// * `question` - The prediction question
     /// * `options` - Array of option labels (2-10 options)
     /// * `stake_amount` - Fixed bet amount in lamports
-    /// * `bet_deadline` - Timestamp when betting closes
+    /// * `bet_deadline` - Timestamp when betting closes (0 when `params.bet_deadline_slot` is set)
     /// * `resolve_time` - Timestamp when settlement can begin
-    /// * `challenge_window` - Duration in seconds for the challenge period
+    /// * `params` - Challenge window and optional per-market overrides, see `CreateMarketParams`
+    ///
+    /// Non-zero fee overrides must lie within the admin-set `[min_fee_bps, max_fee_bps]`.
     pub fn create_market(
         ctx: Context<CreateMarket>,
         question: String,
@@ -84,44 +149,85 @@ pub mod social_bet {
         stake_amount: u64,
         bet_deadline: i64,
         resolve_time: i64,
-        challenge_window: u64,
+        params: CreateMarketParams,
     ) -> Result<()> {
+        let CreateMarketParams {
+            challenge_window,
+            option_fee_bps,
+            clearing_fee_bps,
+            max_payout_multiple,
+            auto_lock_ratio,
+            settle_authority,
+            bet_deadline_slot,
+        } = params;
         let market = &mut ctx.accounts.market;
         let config = &mut ctx.accounts.config;
         let clock = Clock::get()?;
 
         // Validations
+        check_not_excluded(&ctx.accounts.self_exclusion, clock.unix_timestamp)?;
+        check_not_blacklisted(&ctx.accounts.blacklist_entry, config)?;
         require!(question.len() <= MAX_QUESTION_LEN, ErrorCode::QuestionTooLong);
         require!(options.len() >= 2 && options.len() <= MAX_OPTIONS, ErrorCode::InvalidOptionsCount);
         for opt in &options {
             require!(opt.len() <= MAX_OPTION_LEN, ErrorCode::OptionTooLong);
         }
-        require!(stake_amount >= MIN_STAKE_AMOUNT, ErrorCode::StakeTooSmall);
-        require!(bet_deadline > clock.unix_timestamp, ErrorCode::InvalidBetDeadline);
-        require!(resolve_time >= bet_deadline, ErrorCode::InvalidResolveTime);
+        // A zero minimum still requires a real (non-zero) stake
+        require!(stake_amount >= config.min_stake_amount.max(1), ErrorCode::StakeTooSmall);
+        require!(
+            config.max_stake_amount == 0 || stake_amount <= config.max_stake_amount,
+            ErrorCode::StakeTooLarge
+        );
+        match bet_deadline_slot {
+            // Betting closes by slot; a market uses one mode only, so the timestamp stays unset
+            Some(deadline_slot) => {
+                require!(bet_deadline == 0 && deadline_slot > clock.slot, ErrorCode::InvalidBetDeadline);
+                require!(resolve_time > clock.unix_timestamp, ErrorCode::InvalidResolveTime);
+            }
+            None => {
+                require!(bet_deadline > clock.unix_timestamp, ErrorCode::InvalidBetDeadline);
+                require!(resolve_time >= bet_deadline, ErrorCode::InvalidResolveTime);
+            }
+        }
         require!(challenge_window > 0, ErrorCode::InvalidChallengeWindow);
+        require!(
+            option_fee_bps.is_empty() || option_fee_bps.len() == options.len(),
+            ErrorCode::InvalidOptionsCount
+        );
+        for fee_bps in &option_fee_bps {
+            check_fee_override(config, *fee_bps)?;
+        }
+        check_fee_override(config, clearing_fee_bps)?;
+        require!(settle_authority != Some(Pubkey::default()), ErrorCode::InvalidSettleAuthority);
+
+        // Count the market against its creator, enforcing the limit when one is set
+        let creator_stats = &mut ctx.accounts.creator_stats;
+        require!(
+            config.max_markets_per_creator == 0 || creator_stats.active_markets < config.max_markets_per_creator,
+            ErrorCode::TooManyActiveMarkets
+        );
+        creator_stats.creator = ctx.accounts.creator.key();
+        creator_stats.active_markets = creator_stats.active_markets.checked_add(1).ok_or(ErrorCode::Overflow)?;
 
         // Get market ID
         let market_id = config.next_market_id;
         config.next_market_id = config.next_market_id.checked_add(1).ok_or(ErrorCode::Overflow)?;
 
-        // Transfer create fee to fee vault
+        // Transfer create fee to fee vault (and treasury)
         let create_fee = config.create_fee;
-        if create_fee > 0 {
-            anchor_lang::system_program::transfer(
-                CpiContext::new(
-                    ctx.accounts.system_program.to_account_info(),
-                    anchor_lang::system_program::Transfer {
-                        from: ctx.accounts.creator.to_account_info(),
-                        to: ctx.accounts.fee_vault.to_account_info(),
-                    },
-                ),
-                create_fee,
-            )?;
-        }
+        pay_fee(
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.treasury,
+            config,
+            create_fee,
+        )?;
 
         // Initialize market
         market.id = market_id;
+        market.version = MARKET_VERSION;
+        market.bump = ctx.bumps.market;
         market.creator = ctx.accounts.creator.key();
         market.question = question;
         market.options_count = options.len() as u8;
@@ -139,6 +245,12 @@ pub mod social_bet {
         // Initialize vote counts
         market.option_totals = [0u64; MAX_OPTIONS];
         market.option_participants = [0u32; MAX_OPTIONS];
+        market.option_fee_bps = [0u16; MAX_OPTIONS];
+        for (i, fee_bps) in option_fee_bps.iter().enumerate() {
+            market.option_fee_bps[i] = *fee_bps;
+        }
+        market.clearing_fee_bps = clearing_fee_bps;
+        market.max_payout_multiple = max_payout_multiple;
         
         market.stake_amount = stake_amount;
         market.bet_deadline = bet_deadline;
@@ -151,6 +263,21 @@ pub mod social_bet {
         market.final_outcome = None;
         market.created_at = clock.unix_timestamp;
         market.total_pool = 0;
+        market.create_fee_paid = create_fee;
+        market.create_fee_to_vault = split_fee(config, create_fee)?.0;
+        market.claim_deadline = None;
+        market.settle_fee = 0;
+        market.total_claimed = 0;
+        market.distributable = 0;
+        market.winners_remaining = 0;
+        market.winners_paid = 0;
+        market.counts_toward_creator_limit = true;
+        market.auto_lock_ratio = auto_lock_ratio;
+        market.frozen = false;
+        market.total_refunded = 0;
+        market.settle_authority = settle_authority;
+        market.bet_deadline_slot = bet_deadline_slot;
+        market.refund_deadline = refund_deadline(config, market.resolve_time)?;
 
         emit!(MarketCreated {
             market: market.key(),
@@ -167,6 +294,33 @@ pub mod social_bet {
         Ok(())
     }
 
+    /// Attach a description and image to a market (creator only)
+    ///
+    /// Stored in a separate account so settlement never has to load it.
+    pub fn add_market_metadata(
+        ctx: Context<AddMarketMetadata>,
+        _market_id: u64,
+        description: String,
+        image_uri: String,
+    ) -> Result<()> {
+        require!(description.len() <= MAX_DESCRIPTION_LEN, ErrorCode::DescriptionTooLong);
+        require!(image_uri.len() <= MAX_IMAGE_URI_LEN, ErrorCode::ImageUriTooLong);
+
+        let metadata = &mut ctx.accounts.metadata;
+        metadata.market = ctx.accounts.market.key();
+        metadata.description = description;
+        metadata.image_uri = image_uri;
+
+        emit!(MarketMetadataSet {
+            market: metadata.market,
+            market_id: ctx.accounts.market.id,
+            description: metadata.description.clone(),
+            image_uri: metadata.image_uri.clone(),
+        });
+
+        Ok(())
+    }
+
     /// Place a bet on a market option
     /// 
     /// Each address can bet on only ONE option per market.
@@ -181,23 +335,46 @@ pub mod social_bet {
         let config = &ctx.accounts.config;
         let clock = Clock::get()?;
 
+        // Timing first: a bet that lands late is rejected before anything else is touched
+        check_betting_open(market, config, &clock)?;
+
         // Validations
         require!(market.id == market_id, ErrorCode::InvalidMarketId);
         require!(market.status == MarketStatus::Open as u8, ErrorCode::MarketNotOpen);
-        require!(clock.unix_timestamp < market.bet_deadline, ErrorCode::BettingClosed);
+        require!(!market.frozen, ErrorCode::MarketFrozen);
+        check_not_excluded(&ctx.accounts.self_exclusion, clock.unix_timestamp)?;
+        check_not_blacklisted(&ctx.accounts.blacklist_entry, config)?;
         require!((option_index as usize) < market.options_count as usize, ErrorCode::InvalidOptionIndex);
+        check_auto_lock(market, option_index)?;
 
         let stake = market.stake_amount;
 
-        // Calculate join fee
+        if config.enforce_user_limits {
+            let user_limits = ctx.accounts.user_limits.as_mut().ok_or(ErrorCode::UserLimitsRequired)?;
+            user_limits.user = ctx.accounts.user.key();
+            record_daily_stake(user_limits, config, stake, clock.unix_timestamp)?;
+        }
+
+        // Calculate join fee (per-option override falls back to the config default)
+        let join_fee_bps = match market.option_fee_bps[option_index as usize] {
+            0 => config.join_fee_bps,
+            fee_bps => fee_bps,
+        };
         let join_fee = stake
-            .checked_mul(config.join_fee_bps as u64)
+            .checked_mul(join_fee_bps as u64)
             .ok_or(ErrorCode::Overflow)?
             .checked_div(10000)
             .ok_or(ErrorCode::Overflow)?;
+        let join_fee = redeem_fee_credit(&mut ctx.accounts.fee_credit, join_fee);
 
         // Check if this is a new bet or additional bet
         if user_bet.amount == 0 {
+            // Reject with a clear error well before the u32 counters could overflow
+            if config.max_participants > 0 {
+                let participants: u64 = market.option_participants.iter().map(|&p| p as u64).sum();
+                require!(participants < config.max_participants as u64, ErrorCode::MarketFull);
+            }
+
             // First bet - set the option
             user_bet.market_id = market_id;
             user_bet.user = ctx.accounts.user.key();
@@ -205,6 +382,8 @@ pub mod social_bet {
             user_bet.amount = stake;
             user_bet.claimed = false;
             user_bet.bet_count = 1;
+            user_bet.version = USER_BET_VERSION;
+            user_bet.join_fee_paid = join_fee;
 
             // Update market stats
             market.option_participants[option_index as usize] = market.option_participants[option_index as usize]
@@ -214,6 +393,7 @@ pub mod social_bet {
             require!(user_bet.option_index == option_index, ErrorCode::CannotChangeOption);
             user_bet.amount = user_bet.amount.checked_add(stake).ok_or(ErrorCode::Overflow)?;
             user_bet.bet_count = user_bet.bet_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+            user_bet.join_fee_paid = user_bet.join_fee_paid.checked_add(join_fee).ok_or(ErrorCode::Overflow)?;
         }
 
         // Update market pool
@@ -233,19 +413,15 @@ pub mod social_bet {
             stake,
         )?;
 
-        // Transfer join fee to fee vault
-        if join_fee > 0 {
-            anchor_lang::system_program::transfer(
-                CpiContext::new(
-                    ctx.accounts.system_program.to_account_info(),
-                    anchor_lang::system_program::Transfer {
-                        from: ctx.accounts.user.to_account_info(),
-                        to: ctx.accounts.fee_vault.to_account_info(),
-                    },
-                ),
-                join_fee,
-            )?;
-        }
+        // Transfer join fee to fee vault (and treasury)
+        pay_fee(
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.treasury,
+            config,
+            join_fee,
+        )?;
 
         emit!(BetPlaced {
             market: market.key(),
@@ -254,6 +430,64 @@ pub mod social_bet {
             option_index,
             amount: stake,
             total_user_amount: user_bet.amount,
+            fee: join_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Move an existing bet to another option before betting closes
+    ///
+    /// Cheaper than refunding and re-betting: only `switch_fee_bps` of the bet is charged.
+    pub fn switch_option(ctx: Context<SwitchOption>, market_id: u64, new_option_index: u8) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let user_bet = &mut ctx.accounts.user_bet;
+        let config = &ctx.accounts.config;
+        let clock = Clock::get()?;
+
+        // Validations
+        require!(market.status == MarketStatus::Open as u8, ErrorCode::MarketNotOpen);
+        require!(!market.frozen, ErrorCode::MarketFrozen);
+        check_not_excluded(&ctx.accounts.self_exclusion, clock.unix_timestamp)?;
+        check_not_blacklisted(&ctx.accounts.blacklist_entry, config)?;
+        check_betting_open(market, config, &clock)?;
+        require!((new_option_index as usize) < market.options_count as usize, ErrorCode::InvalidOptionIndex);
+        let old_option_index = user_bet.option_index;
+        require!(new_option_index != old_option_index, ErrorCode::SameOption);
+        check_auto_lock(market, new_option_index)?;
+
+        let amount = user_bet.amount;
+        let switch_fee = amount
+            .checked_mul(config.switch_fee_bps as u64)
+            .ok_or(ErrorCode::Overflow)?
+            .checked_div(10000)
+            .ok_or(ErrorCode::Overflow)?;
+
+        // Move the stake and the participant between options
+        let (old, new) = (old_option_index as usize, new_option_index as usize);
+        market.option_totals[old] = market.option_totals[old].checked_sub(amount).ok_or(ErrorCode::Overflow)?;
+        market.option_totals[new] = market.option_totals[new].checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        market.option_participants[old] = market.option_participants[old].checked_sub(1).ok_or(ErrorCode::Overflow)?;
+        market.option_participants[new] = market.option_participants[new].checked_add(1).ok_or(ErrorCode::Overflow)?;
+        user_bet.option_index = new_option_index;
+
+        pay_fee(
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.treasury,
+            config,
+            switch_fee,
+        )?;
+
+        emit!(OptionSwitched {
+            market: market.key(),
+            market_id,
+            user: ctx.accounts.user.key(),
+            from_option: old_option_index,
+            to_option: new_option_index,
+            amount,
+            fee: switch_fee,
         });
 
         Ok(())
@@ -261,7 +495,9 @@ pub mod social_bet {
 
     /// Initiate settlement by proposing an outcome
     /// 
-    /// Can be called at any time by any bettor (early resolution allowed)
+    /// Can be called at any time by any bettor (early resolution allowed), up until
+    /// the market's refund deadline, when it becomes refund-only. Markets with a settle
+    /// authority are settled by it instead.
     pub fn initiate_settlement(
         ctx: Context<InitiateSettlement>,
         market_id: u64,
@@ -275,7 +511,12 @@ pub mod social_bet {
         require!(market.id == market_id, ErrorCode::InvalidMarketId);
         require!(market.status == MarketStatus::Open as u8 || market.status == MarketStatus::Closed as u8, 
             ErrorCode::InvalidMarketStatus);
+        require!(market.settle_authority.is_none(), ErrorCode::SettleAuthorityOnly);
         // Note: Early resolution is allowed - no resolve_time check
+        require!(
+            clock.unix_timestamp < market.refund_deadline,
+            ErrorCode::RefundWindowOpen
+        );
         require!((proposed_outcome as usize) < market.options_count as usize, ErrorCode::InvalidOptionIndex);
         
         // Proposer must have placed a bet
@@ -349,8 +590,19 @@ pub mod social_bet {
         require!(user_bet.amount > 0, ErrorCode::MustBeBettor);
 
         // Finalize with proposed outcome
-        market.status = MarketStatus::Settled as u8;
-        market.final_outcome = market.proposed_outcome;
+        let outcome = market.proposed_outcome.ok_or(ErrorCode::NoOutcome)?;
+        release_creator_slot(market, &mut ctx.accounts.creator_stats)?;
+        if !apply_settlement(
+            market,
+            &ctx.accounts.config,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.treasury,
+            &mut ctx.accounts.outcome_stats,
+            outcome,
+            clock.unix_timestamp,
+        )? {
+            return Ok(());
+        }
 
         emit!(MarketSettled {
             market: market.key(),
@@ -358,6 +610,7 @@ pub mod social_bet {
             outcome: market.final_outcome.unwrap(),
             settled_by: ctx.accounts.caller.key(),
             is_admin_resolution: false,
+            settle_fee: market.settle_fee,
         });
 
         Ok(())
@@ -372,14 +625,25 @@ pub mod social_bet {
         final_outcome: u8,
     ) -> Result<()> {
         let market = &mut ctx.accounts.market;
+        let clock = Clock::get()?;
 
         // Validations (admin check is in account constraint)
         require!(market.status == MarketStatus::Disputed as u8, ErrorCode::MarketNotDisputed);
         require!((final_outcome as usize) < market.options_count as usize, ErrorCode::InvalidOptionIndex);
 
         // Set final outcome
-        market.status = MarketStatus::Settled as u8;
-        market.final_outcome = Some(final_outcome);
+        release_creator_slot(market, &mut ctx.accounts.creator_stats)?;
+        if !apply_settlement(
+            market,
+            &ctx.accounts.config,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.treasury,
+            &mut ctx.accounts.outcome_stats,
+            final_outcome,
+            clock.unix_timestamp,
+        )? {
+            return Ok(());
+        }
 
         emit!(MarketSettled {
             market: market.key(),
@@ -387,77 +651,155 @@ pub mod social_bet {
             outcome: final_outcome,
             settled_by: ctx.accounts.admin.key(),
             is_admin_resolution: true,
+            settle_fee: market.settle_fee,
         });
 
         Ok(())
     }
 
-    /// Claim prize for winning bettors
-    pub fn claim_prize(ctx: Context<ClaimPrize>, market_id: u64) -> Result<()> {
+    /// Admin settles a market nobody proposed an outcome for
+    ///
+    /// Only allowed once `admin_settle_grace` has passed after resolve_time, so bettors
+    /// always get the first chance to settle, and before refunds open.
+    pub fn admin_settle_market(
+        ctx: Context<AdminSettleMarket>,
+        _market_id: u64,
+        final_outcome: u8,
+    ) -> Result<()> {
         let market = &mut ctx.accounts.market;
-        let user_bet = &mut ctx.accounts.user_bet;
         let config = &ctx.accounts.config;
+        let clock = Clock::get()?;
 
-        // Validations
-        require!(market.status == MarketStatus::Settled as u8, ErrorCode::MarketNotSettled);
-        require!(market.final_outcome.is_some(), ErrorCode::NoOutcome);
-        require!(!user_bet.claimed, ErrorCode::AlreadyClaimed);
-        require!(user_bet.option_index == market.final_outcome.unwrap(), ErrorCode::NotWinner);
-
-        // Calculate winnings
-        let winning_option = market.final_outcome.unwrap() as usize;
-        let winning_pool = market.option_totals[winning_option];
-        require!(winning_pool > 0, ErrorCode::NoWinners);
-
-        let total_pool = market.total_pool;
-        
-        // User's share = (user_bet / winning_pool) * total_pool
-        let user_share = (total_pool as u128)
-            .checked_mul(user_bet.amount as u128)
-            .ok_or(ErrorCode::Overflow)?
-            .checked_div(winning_pool as u128)
-            .ok_or(ErrorCode::Overflow)? as u64;
-
-        // Calculate clearing fee
-        let clearing_fee = user_share
-            .checked_mul(config.clearing_fee_bps as u64)
-            .ok_or(ErrorCode::Overflow)?
-            .checked_div(10000)
-            .ok_or(ErrorCode::Overflow)?;
+        // Validations (admin check is in account constraint)
+        require!(
+            market.status == MarketStatus::Open as u8 || market.status == MarketStatus::Closed as u8,
+            ErrorCode::InvalidMarketStatus
+        );
+        require!((final_outcome as usize) < market.options_count as usize, ErrorCode::InvalidOptionIndex);
+        require!(betting_closed(market, &clock), ErrorCode::BettingStillOpen);
+
+        let grace = i64::try_from(config.admin_settle_grace).map_err(|_| ErrorCode::Overflow)?;
+        let grace_end = market.resolve_time.checked_add(grace).ok_or(ErrorCode::Overflow)?;
+        require!(clock.unix_timestamp >= grace_end, ErrorCode::AdminSettleTooEarly);
+        require!(clock.unix_timestamp < market.refund_deadline, ErrorCode::RefundWindowOpen);
+
+        release_creator_slot(market, &mut ctx.accounts.creator_stats)?;
+        if !apply_settlement(
+            market,
+            config,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.treasury,
+            &mut ctx.accounts.outcome_stats,
+            final_outcome,
+            clock.unix_timestamp,
+        )? {
+            return Ok(());
+        }
 
-        let user_payout = user_share.checked_sub(clearing_fee).ok_or(ErrorCode::Overflow)?;
+        emit!(MarketSettledByAdmin {
+            market: market.key(),
+            market_id: market.id,
+            outcome: final_outcome,
+            admin: ctx.accounts.admin.key(),
+            settle_fee: market.settle_fee,
+        });
 
-        // CEI Pattern: Update state BEFORE transfers
-        user_bet.claimed = true;
+        Ok(())
+    }
 
-        // Transfer clearing fee to fee vault
-        if clearing_fee > 0 {
-            let market_balance = market.to_account_info().lamports();
-            require!(market_balance >= clearing_fee, ErrorCode::InsufficientFunds);
-            **market.to_account_info().try_borrow_mut_lamports()? -= clearing_fee;
-            **ctx.accounts.fee_vault.try_borrow_mut_lamports()? += clearing_fee;
-        }
+    /// The market's settle authority settles it directly, with no challenge window
+    ///
+    /// Allowed once betting has closed and before refunds open. The admin can still settle
+    /// through `admin_settle_market` after its grace period if the authority never acts.
+    pub fn authority_settle_market(
+        ctx: Context<AuthoritySettleMarket>,
+        market_id: u64,
+        final_outcome: u8,
+    ) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let config = &ctx.accounts.config;
+        let clock = Clock::get()?;
 
-        // Transfer winnings to user
-        if user_payout > 0 {
-            let market_balance = market.to_account_info().lamports();
-            require!(market_balance >= user_payout, ErrorCode::InsufficientFunds);
-            **market.to_account_info().try_borrow_mut_lamports()? -= user_payout;
-            **ctx.accounts.user.try_borrow_mut_lamports()? += user_payout;
+        // Validations (authority check is in account constraint)
+        require!(
+            market.status == MarketStatus::Open as u8 || market.status == MarketStatus::Closed as u8,
+            ErrorCode::InvalidMarketStatus
+        );
+        require!((final_outcome as usize) < market.options_count as usize, ErrorCode::InvalidOptionIndex);
+        require!(betting_closed(market, &clock), ErrorCode::BettingStillOpen);
+        require!(clock.unix_timestamp < market.refund_deadline, ErrorCode::RefundWindowOpen);
+
+        release_creator_slot(market, &mut ctx.accounts.creator_stats)?;
+        if !apply_settlement(
+            market,
+            config,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.treasury,
+            &mut ctx.accounts.outcome_stats,
+            final_outcome,
+            clock.unix_timestamp,
+        )? {
+            return Ok(());
         }
 
-        emit!(PrizeClaimed {
+        emit!(MarketSettled {
             market: market.key(),
             market_id,
-            user: ctx.accounts.user.key(),
-            amount: user_payout,
-            fee: clearing_fee,
+            outcome: final_outcome,
+            settled_by: ctx.accounts.settle_authority.key(),
+            is_admin_resolution: false,
+            settle_fee: market.settle_fee,
         });
 
         Ok(())
     }
 
-    /// Refund bets if no outcome is proposed within 7 days after resolve_time
+    /// Claim prize for winning bettors
+    ///
+    /// If nobody bet on the winning option, every bettor reclaims their own stake instead.
+    pub fn claim_prize(ctx: Context<ClaimPrize>, market_id: u64) -> Result<()> {
+        let user = ctx.accounts.user.to_account_info();
+        let now = Clock::get()?.unix_timestamp;
+        record_claim(&mut ctx.accounts.user_stats, &ctx.accounts.config, user.key(), now)?;
+        process_claim(
+            &mut ctx.accounts.market,
+            &mut ctx.accounts.user_bet,
+            &ctx.accounts.config,
+            &user,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.treasury,
+            market_id,
+        )?;
+
+        #[cfg(feature = "debug-asserts")]
+        assert_market_solvent(&ctx.accounts.market, now)?;
+
+        Ok(())
+    }
+
+    /// Claim prize and close the UserBet in one go, returning its rent to the user
+    pub fn claim_and_close(ctx: Context<ClaimAndClose>, market_id: u64) -> Result<()> {
+        let user = ctx.accounts.user.to_account_info();
+        let now = Clock::get()?.unix_timestamp;
+        record_claim(&mut ctx.accounts.user_stats, &ctx.accounts.config, user.key(), now)?;
+        // The `close = user` constraint only runs once the claim has succeeded
+        process_claim(
+            &mut ctx.accounts.market,
+            &mut ctx.accounts.user_bet,
+            &ctx.accounts.config,
+            &user,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.treasury,
+            market_id,
+        )?;
+
+        #[cfg(feature = "debug-asserts")]
+        assert_market_solvent(&ctx.accounts.market, now)?;
+
+        Ok(())
+    }
+
+    /// Refund bets if the market is still unsettled at its refund deadline
     pub fn refund_bet(ctx: Context<RefundBet>, market_id: u64) -> Result<()> {
         let market = &mut ctx.accounts.market;
         let user_bet = &mut ctx.accounts.user_bet;
@@ -466,25 +808,25 @@ pub mod social_bet {
         // Validations
         require!(!user_bet.claimed, ErrorCode::AlreadyClaimed);
 
-        // Refund condition: 7 days passed after resolve_time and still OPEN or CLOSED
-        let refund_deadline = market.resolve_time + (7 * 24 * 3600);
+        // Refund condition: refund deadline passed and still OPEN or CLOSED
         require!(
-            clock.unix_timestamp >= refund_deadline && 
+            clock.unix_timestamp >= market.refund_deadline && 
             (market.status == MarketStatus::Open as u8 || market.status == MarketStatus::Closed as u8),
             ErrorCode::RefundNotAvailable
         );
 
         let refund_amount = user_bet.amount;
 
+        // The market is dead once refunds open; the first refund frees the creator's slot
+        release_creator_slot(market, &mut ctx.accounts.creator_stats)?;
+
         // CEI Pattern: Update state BEFORE transfers
         user_bet.claimed = true;
+        market.total_refunded = market.total_refunded.checked_add(refund_amount).ok_or(ErrorCode::Overflow)?;
 
         // Transfer refund to user
         if refund_amount > 0 {
-            let market_balance = market.to_account_info().lamports();
-            require!(market_balance >= refund_amount, ErrorCode::InsufficientFunds);
-            **market.to_account_info().try_borrow_mut_lamports()? -= refund_amount;
-            **ctx.accounts.user.try_borrow_mut_lamports()? += refund_amount;
+            transfer_from_market(&market.to_account_info(), &ctx.accounts.user.to_account_info(), refund_amount)?;
         }
 
         emit!(BetRefunded {
@@ -498,24 +840,211 @@ pub mod social_bet {
     }
 
     /// Admin force-cancel a market and refund all bets
+    ///
+    /// With `refund_create_fee_on_cancel` set, the creator's create fee is also returned
+    /// when the fee vault co-signs and can cover it; otherwise the cancel still goes ahead.
     pub fn admin_cancel_market(ctx: Context<AdminCancelMarket>, _market_id: u64) -> Result<()> {
         let market = &mut ctx.accounts.market;
+        let config = &ctx.accounts.config;
 
         // Validations (admin check is in account constraint)
-        require!(market.status != MarketStatus::Settled as u8, ErrorCode::MarketAlreadySettled);
+        require!(
+            market.status != MarketStatus::Settled as u8 && market.status != MarketStatus::Forfeited as u8,
+            ErrorCode::MarketAlreadySettled
+        );
+        require!(market.status != MarketStatus::Cancelled as u8, ErrorCode::InvalidMarketStatus);
 
         market.status = MarketStatus::Cancelled as u8;
+        market.cancelled_by_admin = true;
+        release_creator_slot(market, &mut ctx.accounts.creator_stats)?;
+
+        let mut create_fee_refunded = 0;
+        // Only the vault's share is refunded; the treasury's share went to an account the
+        // admin can't sign for
+        if config.refund_create_fee_on_cancel && market.create_fee_to_vault > 0 {
+            if let (Some(fee_vault), Some(creator)) = (&ctx.accounts.fee_vault, &ctx.accounts.creator) {
+                // The vault is a system account, so it has to stay rent-exempt
+                let vault_floor = Rent::get()?.minimum_balance(0);
+                let required = market.create_fee_to_vault.checked_add(vault_floor).ok_or(ErrorCode::Overflow)?;
+                if fee_vault.lamports() >= required {
+                    anchor_lang::system_program::transfer(
+                        CpiContext::new(
+                            ctx.accounts.system_program.to_account_info(),
+                            anchor_lang::system_program::Transfer {
+                                from: fee_vault.to_account_info(),
+                                to: creator.to_account_info(),
+                            },
+                        ),
+                        market.create_fee_to_vault,
+                    )?;
+                    create_fee_refunded = market.create_fee_to_vault;
+                }
+            }
+        }
 
         emit!(MarketCancelled {
             market: market.key(),
             market_id: market.id,
             admin: ctx.accounts.admin.key(),
+            create_fee_refunded,
+        });
+
+        Ok(())
+    }
+
+    /// Close a market nobody bet on once its resolve time has passed, returning the rent to
+    /// the creator and freeing their active-market slot
+    ///
+    /// Permissionless: with no bets there is nothing to settle or refund, so anyone can call it.
+    pub fn close_empty_market(ctx: Context<CloseEmptyMarket>, market_id: u64) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let clock = Clock::get()?;
+
+        require!(
+            market.status == MarketStatus::Open as u8 || market.status == MarketStatus::Closed as u8,
+            ErrorCode::InvalidMarketStatus
+        );
+        require!(market.total_pool == 0, ErrorCode::MarketNotEmpty);
+        require!(clock.unix_timestamp >= market.resolve_time, ErrorCode::ResolveTimeNotReached);
+        require!(betting_closed(market, &clock), ErrorCode::BettingStillOpen);
+
+        release_creator_slot(market, &mut ctx.accounts.creator_stats)?;
+
+        // The `close = creator` constraint returns the rent
+        emit!(EmptyMarketClosed {
+            market: market.key(),
+            market_id,
+            creator: market.creator,
+            closed_by: ctx.accounts.caller.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Stop new bets on a single market while leaving settlement and claims open (admin only)
+    pub fn freeze_market(ctx: Context<FreezeMarket>, market_id: u64) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+
+        require!(market.status == MarketStatus::Open as u8, ErrorCode::MarketNotOpen);
+        market.frozen = true;
+
+        emit!(MarketFrozenUpdated { market: market.key(), market_id, frozen: true });
+
+        Ok(())
+    }
+
+    /// Reopen a frozen market to new bets (admin only)
+    pub fn unfreeze_market(ctx: Context<FreezeMarket>, market_id: u64) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        market.frozen = false;
+
+        emit!(MarketFrozenUpdated { market: market.key(), market_id, frozen: false });
+
+        Ok(())
+    }
+
+    /// Upgrade a market written by an older program version to the current layout (admin only)
+    ///
+    /// Fields are only ever appended, and markets are allocated 1500 bytes, so an older market
+    /// deserializes with its newer fields zeroed and never needs to grow. Legacy markets have
+    /// no stored bump, so the account is located by re-deriving the PDA.
+    pub fn migrate_market(ctx: Context<MigrateMarket>, market_id: u64) -> Result<()> {
+        let market_info = ctx.accounts.market.to_account_info();
+        let balance_above_rent = market_info.lamports().saturating_sub(rent_reserve(&market_info)?);
+        let market = &mut ctx.accounts.market;
+        let from_version = market.version;
+
+        upgrade_market(market, &ctx.accounts.config, ctx.bumps.market, balance_above_rent)?;
+
+        emit!(AccountMigrated {
+            account: market.key(),
+            market_id,
+            from_version,
+            to_version: market.version,
+        });
+
+        Ok(())
+    }
+
+    /// Upgrade a legacy config to the current layout (admin only)
+    ///
+    /// The legacy account is too small for the appended settings, so it is grown first, with
+    /// the admin paying the extra rent. The new settings have no safe zero value (a zero
+    /// max_settle_lag makes every market refund-only at resolve_time), so they are passed in.
+    pub fn migrate_config(ctx: Context<MigrateConfig>, params: ConfigParams) -> Result<()> {
+        let config_info = ctx.accounts.config.to_account_info();
+        require_keys_eq!(*config_info.owner, crate::ID, ErrorCode::InvalidAccountOwner);
+        {
+            let data = config_info.try_borrow_data()?;
+            require!(data.len() >= 8 + 32 && data[..8] == <Config as anchor_lang::Discriminator>::DISCRIMINATOR, ErrorCode::InvalidAccountOwner);
+            // admin is the first field in every layout
+            require!(data[8..40] == ctx.accounts.admin.key().to_bytes(), ErrorCode::NotAdmin);
+        }
+
+        grow_account(
+            &config_info,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            CONFIG_SPACE,
+        )?;
+
+        let mut config = Config::try_deserialize(&mut &config_info.try_borrow_data()?[..])?;
+        let from_version = config.version;
+        require!(from_version < ACCOUNT_VERSION, ErrorCode::AccountUpToDate);
+
+        params.validate(&config_info.key())?;
+        config.apply(params);
+        config.bump = ctx.bumps.config;
+        config.version = ACCOUNT_VERSION;
+        config.try_serialize(&mut &mut config_info.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigrated {
+            account: config_info.key(),
+            market_id: 0,
+            from_version,
+            to_version: ACCOUNT_VERSION,
+        });
+
+        Ok(())
+    }
+
+    /// Upgrade a legacy bet account to the current layout (admin only)
+    ///
+    /// Older bets are too short for the appended fields, so the account is grown first. Bets
+    /// placed before `join_fee_paid` was recorded read it as zero and earn no cancel credit.
+    pub fn migrate_user_bet(ctx: Context<MigrateUserBet>, market_id: u64, user: Pubkey) -> Result<()> {
+        let bet_info = ctx.accounts.user_bet.to_account_info();
+        require_keys_eq!(*bet_info.owner, crate::ID, ErrorCode::InvalidAccountOwner);
+
+        grow_account(
+            &bet_info,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            USER_BET_SPACE,
+        )?;
+
+        let mut user_bet = UserBet::try_deserialize(&mut &bet_info.try_borrow_data()?[..])?;
+        require!(user_bet.market_id == market_id && user_bet.user == user, ErrorCode::InvalidMarketId);
+        let from_version = user_bet.version;
+        require!(from_version < USER_BET_VERSION, ErrorCode::AccountUpToDate);
+
+        user_bet.version = USER_BET_VERSION;
+        user_bet.try_serialize(&mut &mut bet_info.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigrated {
+            account: bet_info.key(),
+            market_id,
+            from_version,
+            to_version: USER_BET_VERSION,
         });
 
         Ok(())
     }
 
     /// Claim refund from a cancelled market
+    ///
+    /// Pass a FeeCredit account to also collect `cancel_fee_credit` towards future join fees
+    /// when the admin cancelled the market.
     pub fn claim_cancelled_refund(ctx: Context<ClaimCancelledRefund>, market_id: u64) -> Result<()> {
         let market = &mut ctx.accounts.market;
         let user_bet = &mut ctx.accounts.user_bet;
@@ -527,12 +1056,11 @@ pub mod social_bet {
 
         // CEI Pattern: Update state BEFORE transfers
         user_bet.claimed = true;
+        market.total_refunded = market.total_refunded.checked_add(refund_amount).ok_or(ErrorCode::Overflow)?;
 
         if refund_amount > 0 {
-            let market_balance = market.to_account_info().lamports();
-            require!(market_balance >= refund_amount, ErrorCode::InsufficientFunds);
-            **market.to_account_info().try_borrow_mut_lamports()? -= refund_amount;
-            **ctx.accounts.user.try_borrow_mut_lamports()? += refund_amount;
+            grant_cancel_credit(&mut ctx.accounts.fee_credit, market, user_bet, &ctx.accounts.config)?;
+            transfer_from_market(&market.to_account_info(), &ctx.accounts.user.to_account_info(), refund_amount)?;
         }
 
         emit!(BetRefunded {
@@ -544,47 +1072,1103 @@ pub mod social_bet {
 
         Ok(())
     }
-}
 
-// ============================================================================
-// Account Structures
-// ============================================================================
+    /// Close a UserBet left behind on a cancelled market, returning its rent to the bettor (admin only)
+    ///
+    /// A bettor who never claimed their refund is paid it here as well, so nothing is stranded,
+    /// along with any cancel credit if their FeeCredit account is passed.
+    pub fn close_cancelled_bet(ctx: Context<CloseCancelledBet>, market_id: u64) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let user_bet = &mut ctx.accounts.user_bet;
 
-/// Minimum stake amount (0.001 SOL) to prevent dust attacks
-pub const MIN_STAKE_AMOUNT: u64 = 1_000_000;
+        require!(market.status == MarketStatus::Cancelled as u8, ErrorCode::MarketNotCancelled);
 
-#[account]
-pub struct Config {
-    pub admin: Pubkey,
-    pub fee_vault: Pubkey,
-    pub create_fee: u64,
-    pub join_fee_bps: u16,        // Fee for joining/betting (basis points)
-    pub clearing_fee_bps: u16,    // Fee for claiming prize (basis points)
-    pub settle_fee_bps: u16,      // Fee for settlement (basis points)
-    pub next_market_id: u64,
-}
+        let refund_amount = if user_bet.claimed { 0 } else { user_bet.amount };
 
-#[account]
-pub struct Market {
-    pub id: u64,
-    pub creator: Pubkey,
-    pub question: String,
-    pub options_count: u8,
-    pub options: [String; MAX_OPTIONS],
-    pub option_totals: [u64; MAX_OPTIONS],
-    pub option_participants: [u32; MAX_OPTIONS],
-    pub stake_amount: u64,
-    pub bet_deadline: i64,
-    pub resolve_time: i64,
-    pub challenge_window: u64,
-    pub status: u8,
-    pub proposed_outcome: Option<u8>,
-    pub proposer: Option<Pubkey>,
-    pub challenge_end_time: Option<i64>,
-    pub final_outcome: Option<u8>,
-    pub created_at: i64,
-    pub total_pool: u64,
-}
+        // CEI Pattern: Update state BEFORE transfers
+        user_bet.claimed = true;
+        market.total_refunded = market.total_refunded.checked_add(refund_amount).ok_or(ErrorCode::Overflow)?;
+
+        if refund_amount > 0 {
+            grant_cancel_credit(&mut ctx.accounts.fee_credit, market, user_bet, &ctx.accounts.config)?;
+            transfer_from_market(&market.to_account_info(), &ctx.accounts.user.to_account_info(), refund_amount)?;
+        }
+
+        // The `close = user` constraint returns the rent once the refund has gone through
+        emit!(UserBetClosed {
+            market: market.key(),
+            market_id,
+            user: ctx.accounts.user.key(),
+            refunded: refund_amount,
+            admin: ctx.accounts.admin.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Sweep unclaimed winnings to the fee vault once the claim deadline has passed
+    ///
+    /// Permissionless: anyone can call this after `claim_deadline`
+    pub fn forfeit_unclaimed(ctx: Context<ForfeitUnclaimed>, market_id: u64) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let clock = Clock::get()?;
+
+        require!(market.status == MarketStatus::Settled as u8, ErrorCode::MarketNotSettled);
+        let deadline = market.claim_deadline.ok_or(ErrorCode::NoClaimDeadline)?;
+        require!(clock.unix_timestamp >= deadline, ErrorCode::ClaimWindowNotEnded);
+
+        // Leave the account rent-exempt, sweep everything else
+        let market_info = market.to_account_info();
+        let amount = market_info.lamports().saturating_sub(rent_reserve(&market_info)?);
+
+        // CEI Pattern: Update state BEFORE transfers
+        market.status = MarketStatus::Forfeited as u8;
+        market.distributable = 0;
+
+        if amount > 0 {
+            transfer_from_market(&market.to_account_info(), &ctx.accounts.fee_vault.to_account_info(), amount)?;
+        }
+
+        emit!(WinningsForfeited {
+            market: market.key(),
+            market_id,
+            amount,
+            caller: ctx.accounts.caller.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Block yourself from creating markets and betting for `duration` seconds
+    ///
+    /// An exclusion can be extended but never shortened, so it can't be undone on impulse.
+    pub fn self_exclude(ctx: Context<SelfExclude>, duration: u64) -> Result<()> {
+        let exclusion = &mut ctx.accounts.self_exclusion;
+        let clock = Clock::get()?;
+
+        let duration = i64::try_from(duration).map_err(|_| ErrorCode::Overflow)?;
+        let excluded_until = clock.unix_timestamp.checked_add(duration).ok_or(ErrorCode::Overflow)?;
+        require!(excluded_until >= exclusion.excluded_until, ErrorCode::ExclusionCannotBeShortened);
+
+        exclusion.user = ctx.accounts.user.key();
+        exclusion.excluded_until = excluded_until;
+
+        emit!(SelfExclusionSet {
+            user: exclusion.user,
+            excluded_until,
+        });
+
+        Ok(())
+    }
+
+    /// Report what each `(market, user_bet)` pair in `remaining_accounts` can claim
+    ///
+    /// Read-only: emits one `ClaimScanResult` per pair, so clients can offer
+    /// "claim all" without duplicating the settlement and fee math.
+    pub fn scan_claims<'info>(ctx: Context<'_, '_, 'info, 'info, ScanClaims<'info>>) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let clock = Clock::get()?;
+
+        // Pairs only: an empty list or a trailing unpaired account is a client bug
+        let pairs = ctx.remaining_accounts.chunks_exact(2);
+        require!(
+            !ctx.remaining_accounts.is_empty() && pairs.remainder().is_empty(),
+            ErrorCode::InvalidScanAccounts
+        );
+
+        for pair in pairs {
+            let market = Account::<Market>::try_from(&pair[0])?;
+            let user_bet = Account::<UserBet>::try_from(&pair[1])?;
+            require!(user_bet.market_id == market.id, ErrorCode::InvalidMarketId);
+
+            let (status, amount) = scan_claim(&market, &user_bet, config, clock.unix_timestamp)?;
+
+            emit!(ClaimScanResult {
+                market: market.key(),
+                market_id: market.id,
+                user: user_bet.user,
+                status: status as u8,
+                amount,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Return a compact summary of a market as return data for CPI callers
+    pub fn get_market_summary(ctx: Context<GetMarketSummary>, _market_id: u64) -> Result<MarketSummary> {
+        let market = &ctx.accounts.market;
+
+        Ok(MarketSummary {
+            id: market.id,
+            status: market.status,
+            options_count: market.options_count,
+            option_totals: market.option_totals,
+            total_pool: market.total_pool,
+            bet_deadline: market.bet_deadline,
+            final_outcome: market.final_outcome,
+            bet_deadline_slot: market.bet_deadline_slot,
+        })
+    }
+
+    /// Return whether a bet backed the winning option, as return data for CPI callers
+    pub fn check_winner(ctx: Context<CheckWinner>, _market_id: u64) -> Result<bool> {
+        let market = &ctx.accounts.market;
+
+        require!(market.status == MarketStatus::Settled as u8, ErrorCode::MarketNotSettled);
+
+        Ok(is_winning_bet(market, &ctx.accounts.user_bet))
+    }
+
+}
+
+/// Whether `user_bet` backed the winning option of a settled market
+pub fn is_winning_bet(market: &Market, user_bet: &UserBet) -> bool {
+    market.status == MarketStatus::Settled as u8
+        && user_bet.market_id == market.id
+        && market.final_outcome == Some(user_bet.option_index)
+}
+
+/// Validate a creator-chosen fee override; zero means "use the config default"
+fn check_fee_override(config: &Config, fee_bps: u16) -> Result<()> {
+    if fee_bps == 0 {
+        return Ok(());
+    }
+    require!(fee_bps <= 10000, ErrorCode::InvalidFeeBps);
+    require!(
+        fee_bps >= config.min_fee_bps && fee_bps <= config.max_fee_bps,
+        ErrorCode::FeeOutOfBounds
+    );
+    Ok(())
+}
+
+/// Reject users with an active self-exclusion; a missing account means not excluded
+fn check_not_excluded(self_exclusion: &AccountInfo, now: i64) -> Result<()> {
+    if self_exclusion.owner != &crate::ID || self_exclusion.data_is_empty() {
+        return Ok(());
+    }
+
+    let data = self_exclusion.try_borrow_data()?;
+    let exclusion = SelfExclusion::try_deserialize(&mut &data[..])?;
+    require!(now >= exclusion.excluded_until, ErrorCode::SelfExcluded);
+
+    Ok(())
+}
+
+/// Stop counting a market toward its creator's active-market limit, once
+///
+/// Markets created before the limit existed were never counted and need no stats account.
+fn release_creator_slot(market: &mut Market, creator_stats: &mut Option<Account<CreatorStats>>) -> Result<()> {
+    if !market.counts_toward_creator_limit {
+        return Ok(());
+    }
+
+    let creator_stats = creator_stats.as_mut().ok_or(ErrorCode::CreatorStatsRequired)?;
+    creator_stats.active_markets = creator_stats.active_markets.saturating_sub(1);
+    market.counts_toward_creator_limit = false;
+
+    Ok(())
+}
+
+/// Reject blacklisted users while the blacklist is enabled; a missing account means not listed
+fn check_not_blacklisted(blacklist_entry: &AccountInfo, config: &Config) -> Result<()> {
+    let listed = blacklist_entry.owner == &crate::ID && !blacklist_entry.data_is_empty();
+    require!(!(config.blacklist_enabled && listed), ErrorCode::UserBlacklisted);
+    Ok(())
+}
+
+/// Reject new stake on an option that already holds more than `auto_lock_ratio` times the
+/// rest of the market, so a crowded side can't swamp the others. Only applies once some
+/// other option has stake.
+fn check_auto_lock(market: &Market, option_index: u8) -> Result<()> {
+    if market.auto_lock_ratio == 0 {
+        return Ok(());
+    }
+
+    let option_total = market.option_totals[option_index as usize];
+    let rest = market.total_pool.checked_sub(option_total).ok_or(ErrorCode::Overflow)?;
+    require!(
+        rest == 0 || option_total as u128 <= rest as u128 * market.auto_lock_ratio as u128,
+        ErrorCode::AutoLocked
+    );
+
+    Ok(())
+}
+
+/// Add `stake` to the user's total for the current UTC day, resetting it when a new day starts
+fn record_daily_stake(limits: &mut UserLimits, config: &Config, stake: u64, now: i64) -> Result<()> {
+    let today = now - now.rem_euclid(SECONDS_PER_DAY);
+    if limits.day_start != today {
+        limits.day_start = today;
+        limits.staked_today = 0;
+    }
+
+    let staked_today = limits.staked_today.checked_add(stake).ok_or(ErrorCode::Overflow)?;
+    require!(staked_today <= config.max_daily_stake, ErrorCode::DailyLimitExceeded);
+    limits.staked_today = staked_today;
+
+    Ok(())
+}
+
+/// Join-fee credit owed for a refunded bet: only on an admin cancellation, and never more
+/// than the join fee the bet actually paid, so auto-cancelled markets can't be farmed
+fn cancel_credit(config_credit: u64, cancelled_by_admin: bool, join_fee_paid: u64) -> u64 {
+    if cancelled_by_admin {
+        config_credit.min(join_fee_paid)
+    } else {
+        0
+    }
+}
+
+/// Add the cancel credit for a refunded bet to the bettor's FeeCredit, if one was passed
+fn grant_cancel_credit(
+    fee_credit: &mut Option<Account<FeeCredit>>,
+    market: &Market,
+    user_bet: &UserBet,
+    config: &Config,
+) -> Result<()> {
+    let credit = cancel_credit(config.cancel_fee_credit, market.cancelled_by_admin, user_bet.join_fee_paid);
+    if credit == 0 {
+        return Ok(());
+    }
+
+    if let Some(fee_credit) = fee_credit.as_mut() {
+        fee_credit.user = user_bet.user;
+        fee_credit.balance = fee_credit.balance.checked_add(credit).ok_or(ErrorCode::Overflow)?;
+
+        emit!(FeeCreditGranted {
+            user: fee_credit.user,
+            market_id: market.id,
+            amount: credit,
+            balance: fee_credit.balance,
+        });
+    }
+
+    Ok(())
+}
+
+/// Offset `fee` with the user's fee credit, if any, returning what is still owed
+fn redeem_fee_credit(fee_credit: &mut Option<Account<FeeCredit>>, fee: u64) -> u64 {
+    match fee_credit {
+        Some(fee_credit) => {
+            let used = fee_credit.balance.min(fee);
+            fee_credit.balance -= used;
+            fee - used
+        }
+        None => fee,
+    }
+}
+
+/// Enforce `claim_cooldown` between a user's claims, recording this one
+fn record_claim(user_stats: &mut Option<Account<UserStats>>, config: &Config, user: Pubkey, now: i64) -> Result<()> {
+    if config.claim_cooldown == 0 {
+        return Ok(());
+    }
+
+    let user_stats = user_stats.as_mut().ok_or(ErrorCode::UserStatsRequired)?;
+    let cooldown = i64::try_from(config.claim_cooldown).map_err(|_| ErrorCode::Overflow)?;
+    let next_claim_at = user_stats.last_claim_at.checked_add(cooldown).ok_or(ErrorCode::Overflow)?;
+    require!(user_stats.last_claim_at == 0 || now >= next_claim_at, ErrorCode::ClaimTooSoon);
+
+    user_stats.user = user;
+    user_stats.last_claim_at = now;
+
+    Ok(())
+}
+
+/// Fail with `InsolventMarket` if a market holds less than it still owes
+///
+/// Live markets owe every stake, settled ones whatever is left of the claim snapshot.
+/// Once refunds are open the owed total isn't tracked, so only rent is checked. Only
+/// compiled in with the `debug-asserts` feature, where every claim runs it.
+#[cfg(feature = "debug-asserts")]
+fn assert_market_solvent(market: &Account<Market>, now: i64) -> Result<()> {
+    let live = market.status == MarketStatus::Open as u8
+        || market.status == MarketStatus::Closed as u8
+        || market.status == MarketStatus::Proposed as u8
+        || market.status == MarketStatus::Disputed as u8;
+    let owed = if live && now < market.refund_deadline {
+        market.total_pool
+    } else if market.status == MarketStatus::Settled as u8 {
+        market.distributable
+    } else {
+        0
+    };
+
+    let market_info = market.to_account_info();
+    let required = rent_reserve(&market_info)?.checked_add(owed).ok_or(ErrorCode::Overflow)?;
+    require!(market_info.lamports() >= required, ErrorCode::InsolventMarket);
+
+    Ok(())
+}
+
+/// Pay out a claim on a settled market, shared by `claim_prize` and `claim_and_close`
+fn process_claim<'info>(
+    market: &mut Account<'info, Market>,
+    user_bet: &mut Account<'info, UserBet>,
+    config: &Config,
+    user: &AccountInfo<'info>,
+    fee_vault: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    market_id: u64,
+) -> Result<()> {
+    // Validations
+    require!(market.status == MarketStatus::Settled as u8, ErrorCode::MarketNotSettled);
+    require!(market.final_outcome.is_some(), ErrorCode::NoOutcome);
+    require!(!user_bet.claimed, ErrorCode::AlreadyClaimed);
+
+    let winning_option = market.final_outcome.unwrap() as usize;
+    let winning_pool = market.option_totals[winning_option];
+
+    // Nobody backed the winning option: return each bettor's own stake
+    // instead of leaving the pool stuck forever
+    if winning_pool == 0 {
+        let refund_amount = user_bet.amount;
+        draw_distributable(market, refund_amount)?;
+
+        // CEI Pattern: Update state BEFORE transfers
+        user_bet.claimed = true;
+
+        if refund_amount > 0 {
+            transfer_from_market(&market.to_account_info(), &user.to_account_info(), refund_amount)?;
+        }
+
+        emit!(WinningOptionEmpty {
+            market: market.key(),
+            market_id,
+            winning_option: winning_option as u8,
+            user: user.key(),
+            refunded: refund_amount,
+        });
+
+        return Ok(());
+    }
+
+    // Losing bets only get something back when the payout cap left an overflow
+    if user_bet.option_index != winning_option as u8 {
+        let refund_amount = cap_overflow_refund(market, user_bet.amount)?;
+        require!(refund_amount > 0, ErrorCode::NotWinner);
+
+        let prize_pool = market.total_pool.checked_sub(market.settle_fee).ok_or(ErrorCode::Overflow)?;
+        market.total_claimed = market.total_claimed.checked_add(refund_amount).ok_or(ErrorCode::Overflow)?;
+        require!(market.total_claimed <= prize_pool, ErrorCode::InsufficientFunds);
+        draw_distributable(market, refund_amount)?;
+
+        // CEI Pattern: Update state BEFORE transfers
+        user_bet.claimed = true;
+
+        transfer_from_market(&market.to_account_info(), &user.to_account_info(), refund_amount)?;
+
+        emit!(PayoutCapRefunded {
+            market: market.key(),
+            market_id,
+            user: user.key(),
+            amount: refund_amount,
+        });
+
+        return Ok(());
+    }
+
+    // Calculate winnings
+
+    let total_pool = market.total_pool;
+    let prize_pool = total_pool.checked_sub(market.settle_fee).ok_or(ErrorCode::Overflow)?;
+    let (user_share, clearing_fee) = winning_share(market, config, user_bet.amount)?;
+    let (winners_pool, _) = split_prize_pool(market)?;
+    let last_winner = market.winners_remaining == 1;
+
+    market.total_claimed = market.total_claimed.checked_add(user_share).ok_or(ErrorCode::Overflow)?;
+    require!(market.total_claimed <= prize_pool, ErrorCode::InsufficientFunds);
+    draw_distributable(market, user_share)?;
+    market.winners_paid = market.winners_paid.checked_add(user_share).ok_or(ErrorCode::Overflow)?;
+    market.winners_remaining = market.winners_remaining.saturating_sub(1);
+
+    let user_payout = user_share.checked_sub(clearing_fee).ok_or(ErrorCode::Overflow)?;
+
+    // CEI Pattern: Update state BEFORE transfers
+    user_bet.claimed = true;
+
+    // Transfer clearing fee to fee vault (and treasury)
+    take_market_fee(&market.to_account_info(), fee_vault, treasury, config, clearing_fee)?;
+
+    // Transfer winnings to user
+    if user_payout > 0 {
+        transfer_from_market(&market.to_account_info(), &user.to_account_info(), user_payout)?;
+    }
+
+    emit!(PrizeClaimed {
+        market: market.key(),
+        market_id,
+        user: user.key(),
+        amount: user_payout,
+        fee: clearing_fee,
+        user_bet_amount: user_bet.amount,
+        winning_pool,
+        total_pool,
+        settle_fee: market.settle_fee,
+        winners_pool,
+        last_winner,
+    });
+
+    Ok(())
+}
+
+/// Walk a market forward to MARKET_VERSION, one version per step. `bump` is the re-derived
+/// PDA bump, `balance_above_rent` what the account holds beyond its rent floor, and `config`
+/// supplies the settlement lag older markets' refund deadlines are fixed at.
+fn upgrade_market(market: &mut Market, config: &Config, bump: u8, balance_above_rent: u64) -> Result<()> {
+    require!(market.version < MARKET_VERSION, ErrorCode::AccountUpToDate);
+
+    while market.version < MARKET_VERSION {
+        match market.version {
+            // Legacy market from before versioning. Its appended fields read as zero, which
+            // means default fees, no payout cap and no create fee to refund; only the bump,
+            // which was never stored, needs filling in.
+            0 => {
+                market.bump = bump;
+                market.version = 1;
+            }
+            // v2 adds the claim snapshot; a settled market sets aside what is left above rent
+            1 => {
+                if market.status == MarketStatus::Settled as u8 {
+                    market.distributable = balance_above_rent;
+                }
+                market.version = 2;
+            }
+            // v3 adds last-winner dust consolidation. Unclaimed winners of an already
+            // settled market can't be counted, so those keep plain round-down payouts.
+            2 => market.version = 3,
+            // v4 adds the creator limit flag; older markets were never counted
+            3 => market.version = 4,
+            // v5 adds the auto-lock ratio, read as zero (off) on older markets
+            4 => market.version = 5,
+            // v6 adds the frozen flag; older markets were never frozen
+            5 => market.version = 6,
+            // v7 adds the refund total. Refunds paid before the upgrade weren't
+            // recorded, so it only counts refunds from here on.
+            6 => market.version = 7,
+            // v8 records the fee vault's share of the create fee. Older markets don't know
+            // the split they were charged, so they read zero and get no refund on cancel.
+            7 => market.version = 8,
+            // v9 records admin cancellations. Older cancelled markets can't be told apart
+            // from auto-cancels, so they read false and grant no fee credit.
+            8 => market.version = 9,
+            // v10 adds the settle authority; older markets read None and keep bettor proposals
+            9 => market.version = 10,
+            // v11 adds slot-based betting close; older markets read None and close by timestamp
+            10 => market.version = 11,
+            // v12 stores the refund deadline. Older markets followed the live max_settle_lag,
+            // so they take the current one, fixed from here on.
+            11 => {
+                market.refund_deadline = refund_deadline(config, market.resolve_time)?;
+                market.version = 12;
+            }
+            _ => return err!(ErrorCode::UnknownAccountVersion),
+        }
+    }
+
+    Ok(())
+}
+
+/// Grow a program-owned account to `new_len`, topping its rent up from `payer`
+fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    if account.data_len() >= new_len {
+        return Ok(());
+    }
+
+    let shortfall = Rent::get()?.minimum_balance(new_len).saturating_sub(account.lamports());
+    if shortfall > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    account.realloc(new_len, true)?;
+
+    Ok(())
+}
+
+/// Take `amount` out of the payout snapshot, failing rather than promising more than was set aside
+fn draw_distributable(market: &mut Market, amount: u64) -> Result<()> {
+    market.distributable = market.distributable.checked_sub(amount).ok_or(ErrorCode::InsufficientFunds)?;
+    Ok(())
+}
+
+/// Gross share and clearing fee for a winning bet of `amount` on a settled market
+fn winning_share(market: &Market, config: &Config, amount: u64) -> Result<(u64, u64)> {
+    let winning_option = market.final_outcome.ok_or(ErrorCode::NoOutcome)? as usize;
+    let winning_pool = market.option_totals[winning_option];
+    let (winners_pool, _) = split_prize_pool(market)?;
+
+    // User's share = (user_bet / winning_pool) * winners_pool
+    //
+    // Rounding policy: shares round down, so the sum of all shares can never exceed
+    // winners_pool. The last winner to claim takes whatever the others left instead,
+    // so the rounding dust goes to them rather than staying in the market.
+    let user_share = if market.winners_remaining == 1 {
+        winners_pool.checked_sub(market.winners_paid).ok_or(ErrorCode::Overflow)?
+    } else {
+        let share = (winners_pool as u128)
+            .checked_mul(amount as u128)
+            .ok_or(ErrorCode::Overflow)?
+            .checked_div(winning_pool as u128)
+            .ok_or(ErrorCode::Overflow)?;
+        u64::try_from(share).map_err(|_| ErrorCode::Overflow)?
+    };
+
+    // Calculate clearing fee (market override falls back to the config default)
+    let clearing_fee_bps = match market.clearing_fee_bps {
+        0 => config.clearing_fee_bps,
+        fee_bps => fee_bps,
+    };
+    let clearing_fee = user_share
+        .checked_mul(clearing_fee_bps as u64)
+        .ok_or(ErrorCode::Overflow)?
+        .checked_div(10000)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok((user_share, clearing_fee))
+}
+
+/// Split `total_pool - settle_fee` into what the winners share and the cap overflow
+///
+/// With `max_payout_multiple` set, winners together receive at most
+/// `winning_pool * max_payout_multiple`. Everything above that is the overflow, which is
+/// returned to the losing side pro rata to their stakes rather than kept by the protocol.
+fn split_prize_pool(market: &Market) -> Result<(u64, u64)> {
+    let winning_option = market.final_outcome.ok_or(ErrorCode::NoOutcome)? as usize;
+    let winning_pool = market.option_totals[winning_option];
+    let prize_pool = market.total_pool.checked_sub(market.settle_fee).ok_or(ErrorCode::Overflow)?;
+
+    if market.max_payout_multiple == 0 {
+        return Ok((prize_pool, 0));
+    }
+
+    let cap = (winning_pool as u128)
+        .checked_mul(market.max_payout_multiple as u128)
+        .ok_or(ErrorCode::Overflow)?;
+    let winners_pool = u64::try_from(cap.min(prize_pool as u128)).map_err(|_| ErrorCode::Overflow)?;
+
+    Ok((winners_pool, prize_pool - winners_pool))
+}
+
+/// A losing bet's pro-rata slice of the payout cap overflow (rounded down)
+fn cap_overflow_refund(market: &Market, amount: u64) -> Result<u64> {
+    let (_, overflow) = split_prize_pool(market)?;
+    if overflow == 0 {
+        return Ok(0);
+    }
+
+    let winning_option = market.final_outcome.ok_or(ErrorCode::NoOutcome)? as usize;
+    let losing_pool = market.total_pool
+        .checked_sub(market.option_totals[winning_option])
+        .ok_or(ErrorCode::Overflow)?;
+
+    let refund = (overflow as u128)
+        .checked_mul(amount as u128)
+        .ok_or(ErrorCode::Overflow)?
+        .checked_div(losing_pool as u128)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(u64::try_from(refund).map_err(|_| ErrorCode::Overflow)?)
+}
+
+/// Classify one bet for `scan_claims`, returning the scan status and the net amount payable
+///
+/// Mirrors the checks in `process_claim`, `claim_cancelled_refund` and `refund_bet`
+/// without moving any lamports.
+fn scan_claim(market: &Market, user_bet: &UserBet, config: &Config, now: i64) -> Result<(ClaimScanStatus, u64)> {
+    if user_bet.claimed {
+        return Ok((ClaimScanStatus::AlreadyClaimed, 0));
+    }
+
+    if market.status == MarketStatus::Cancelled as u8 {
+        return Ok((ClaimScanStatus::Refundable, user_bet.amount));
+    }
+    if market.status == MarketStatus::Forfeited as u8 {
+        return Ok((ClaimScanStatus::Forfeited, 0));
+    }
+    if market.status != MarketStatus::Settled as u8 {
+        let refundable = (market.status == MarketStatus::Open as u8
+            || market.status == MarketStatus::Closed as u8)
+            && now >= market.refund_deadline;
+        if refundable {
+            return Ok((ClaimScanStatus::Refundable, user_bet.amount));
+        }
+        return Ok((ClaimScanStatus::NotSettled, 0));
+    }
+
+    let winning_option = market.final_outcome.ok_or(ErrorCode::NoOutcome)?;
+    if market.option_totals[winning_option as usize] == 0 {
+        return Ok((ClaimScanStatus::Claimable, user_bet.amount));
+    }
+    if !is_winning_bet(market, user_bet) {
+        let refund_amount = cap_overflow_refund(market, user_bet.amount)?;
+        if refund_amount > 0 {
+            return Ok((ClaimScanStatus::Refundable, refund_amount));
+        }
+        return Ok((ClaimScanStatus::Lost, 0));
+    }
+
+    let (user_share, clearing_fee) = winning_share(market, config, user_bet.amount)?;
+    let user_payout = user_share.checked_sub(clearing_fee).ok_or(ErrorCode::Overflow)?;
+    Ok((ClaimScanStatus::Claimable, user_payout))
+}
+
+/// Settle a market on `final_outcome`, returning `false` if it was refunded instead
+///
+/// With `require_contested` set, a market where fewer than two options have stake has no
+/// real counterparty, so it is cancelled without fees and bettors reclaim their stake
+/// through `claim_cancelled_refund`. The same happens to a market with fewer than
+/// `min_distinct_participants` bettors.
+fn apply_settlement<'info>(
+    market: &mut Account<'info, Market>,
+    config: &Config,
+    fee_vault: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    outcome_stats: &mut OutcomeStats,
+    final_outcome: u8,
+    now: i64,
+) -> Result<bool> {
+    let contested_options = market.option_totals.iter().filter(|&&total| total > 0).count();
+    let participants: u64 = market.option_participants.iter().map(|&p| p as u64).sum();
+    if (config.require_contested && contested_options < 2)
+        || participants < config.min_distinct_participants as u64
+    {
+        market.status = MarketStatus::Cancelled as u8;
+        outcome_stats.uncontested_refunds = outcome_stats.uncontested_refunds.checked_add(1).ok_or(ErrorCode::Overflow)?;
+
+        emit!(UncontestedMarketRefunded {
+            market: market.key(),
+            market_id: market.id,
+            total_pool: market.total_pool,
+        });
+
+        return Ok(false);
+    }
+
+    market.status = MarketStatus::Settled as u8;
+    market.final_outcome = Some(final_outcome);
+    market.claim_deadline = claim_deadline(config, now)?;
+    market.winners_remaining = market.option_participants[final_outcome as usize];
+    market.winners_paid = 0;
+
+    outcome_stats.settled_markets = outcome_stats.settled_markets.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    outcome_stats.outcome_counts[final_outcome as usize] = outcome_stats.outcome_counts[final_outcome as usize]
+        .checked_add(1).ok_or(ErrorCode::Overflow)?;
+    outcome_stats.settled_volume = outcome_stats.settled_volume.checked_add(market.total_pool).ok_or(ErrorCode::Overflow)?;
+    charge_settle_fee(market, config, fee_vault, treasury)?;
+
+    // Snapshot what claims may draw on, so payouts never depend on the live balance
+    let market_info = market.to_account_info();
+    market.distributable = market_info.lamports().saturating_sub(rent_reserve(&market_info)?);
+
+    Ok(true)
+}
+
+/// Take the settlement fee from a market that has just been settled
+///
+/// The fee is `settle_fee_bps` of the pool, raised to `min_settle_fee`, and capped at
+/// what the market can pay above rent. Nothing is charged when the winning option is
+/// empty, since every bettor is then refunded in full.
+fn charge_settle_fee<'info>(
+    market: &mut Account<'info, Market>,
+    config: &Config,
+    fee_vault: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+) -> Result<()> {
+    let winning_option = market.final_outcome.ok_or(ErrorCode::NoOutcome)? as usize;
+    if market.option_totals[winning_option] == 0 {
+        return Ok(());
+    }
+
+    let market_info = market.to_account_info();
+    let available = market_info.lamports().saturating_sub(rent_reserve(&market_info)?);
+    let (expected_fee, settle_fee) = settle_fee_due(market.total_pool, config, available)?;
+
+    // Never block settlement on the fee: take what the balance allows and flag the gap
+    if settle_fee < expected_fee {
+        emit!(SettleFeeShortfall {
+            market: market.key(),
+            market_id: market.id,
+            expected_fee,
+            charged_fee: settle_fee,
+        });
+    }
+
+    // CEI Pattern: Update state BEFORE transfers
+    market.settle_fee = settle_fee;
+
+    take_market_fee(&market.to_account_info(), fee_vault, treasury, config, settle_fee)?;
+
+    Ok(())
+}
+
+/// Settlement fee owed on `total_pool` and the part of it `available` can cover, as
+/// `(expected_fee, settle_fee)`
+fn settle_fee_due(total_pool: u64, config: &Config, available: u64) -> Result<(u64, u64)> {
+    let bps_fee = total_pool
+        .checked_mul(config.settle_fee_bps as u64)
+        .ok_or(ErrorCode::Overflow)?
+        .checked_div(10000)
+        .ok_or(ErrorCode::Overflow)?;
+
+    let expected_fee = bps_fee.max(config.min_settle_fee).min(total_pool);
+    Ok((expected_fee, expected_fee.min(available)))
+}
+
+/// Split a collected fee into the operator vault's part and the protocol treasury's part
+fn split_fee(config: &Config, fee: u64) -> Result<(u64, u64)> {
+    let treasury_fee = fee
+        .checked_mul(config.treasury_split_bps as u64)
+        .ok_or(ErrorCode::Overflow)?
+        .checked_div(10000)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok((fee - treasury_fee, treasury_fee))
+}
+
+/// Pay a fee from a signer, split between the fee vault and the treasury
+fn pay_fee<'info>(
+    system_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    fee_vault: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    config: &Config,
+    fee: u64,
+) -> Result<()> {
+    let (vault_fee, treasury_fee) = split_fee(config, fee)?;
+    for (to, amount) in [(fee_vault, vault_fee), (treasury, treasury_fee)] {
+        if amount > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    anchor_lang::system_program::Transfer {
+                        from: from.clone(),
+                        to: to.clone(),
+                    },
+                ),
+                amount,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Move a fee out of a market, split between the fee vault and the treasury
+///
+/// Fails with `InsufficientForFee` rather than `InsufficientFunds`, so clients can tell a
+/// market that can't cover the protocol fee from one that can't pay the user.
+fn take_market_fee<'info>(
+    market: &AccountInfo<'info>,
+    fee_vault: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    config: &Config,
+    fee: u64,
+) -> Result<()> {
+    // A market paying itself would count the fee as taken while keeping the lamports
+    require!(fee_vault.key() != market.key(), ErrorCode::InvalidFeeVault);
+    require!(treasury.key() != market.key(), ErrorCode::InvalidTreasury);
+
+    check_fee_payable(market.lamports(), rent_reserve(market)?, fee)?;
+
+    let (vault_fee, treasury_fee) = split_fee(config, fee)?;
+    transfer_from_market(market, fee_vault, vault_fee)?;
+    transfer_from_market(market, treasury, treasury_fee)
+}
+
+/// Move `amount` lamports out of a program-owned market account
+///
+/// The market must stay rent-exempt after the transfer, so the move fails with
+/// `InsufficientFunds` if it would dip into the rent reserve.
+fn transfer_from_market<'info>(
+    market: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    check_payable(market.lamports(), rent_reserve(market)?, amount)?;
+
+    **market.try_borrow_mut_lamports()? -= amount;
+    **to.try_borrow_mut_lamports()? += amount;
+
+    Ok(())
+}
+
+/// Fail with `InsufficientFunds` unless `amount` can leave an account holding `balance`
+/// without taking it below `rent_exempt_minimum`
+fn check_payable(balance: u64, rent_exempt_minimum: u64, amount: u64) -> Result<()> {
+    require!(balance.saturating_sub(rent_exempt_minimum) >= amount, ErrorCode::InsufficientFunds);
+    Ok(())
+}
+
+/// `check_payable` for protocol fees, failing with `InsufficientForFee` instead
+fn check_fee_payable(balance: u64, rent_exempt_minimum: u64, fee: u64) -> Result<()> {
+    require!(balance.saturating_sub(rent_exempt_minimum) >= fee, ErrorCode::InsufficientForFee);
+    Ok(())
+}
+
+/// Lamports an account must keep to stay rent-exempt
+fn rent_reserve(account: &AccountInfo) -> Result<u64> {
+    Ok(Rent::get()?.minimum_balance(account.data_len()))
+}
+
+/// Reject a bet or option switch once betting has closed
+///
+/// Betting closes at `bet_deadline`, or at `bet_deadline_slot` for slot-based markets. New
+/// bets stop `join_cutoff` earlier so late bets can't front-run resolution; for slot-based
+/// markets the cutoff is converted at the nominal slot time. A slot-based market also closes
+/// at resolve_time if the slot hasn't been reached by then.
+fn check_betting_open(market: &Market, config: &Config, clock: &Clock) -> Result<()> {
+    match market.bet_deadline_slot {
+        Some(deadline_slot) => {
+            require!(
+                clock.slot < deadline_slot && clock.unix_timestamp < market.resolve_time,
+                ErrorCode::BettingClosed
+            );
+            let cutoff_slots = config.join_cutoff.saturating_mul(1000) / DEFAULT_MS_PER_SLOT;
+            require!(clock.slot < deadline_slot.saturating_sub(cutoff_slots), ErrorCode::JoinWindowClosed);
+        }
+        None => {
+            require!(clock.unix_timestamp < market.bet_deadline, ErrorCode::BettingClosed);
+            let join_cutoff = i64::try_from(config.join_cutoff).map_err(|_| ErrorCode::Overflow)?;
+            require!(
+                clock.unix_timestamp < market.bet_deadline.saturating_sub(join_cutoff),
+                ErrorCode::JoinWindowClosed
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Whether betting has closed, by slot (or resolve_time) for slot-based markets and by
+/// timestamp otherwise
+fn betting_closed(market: &Market, clock: &Clock) -> bool {
+    match market.bet_deadline_slot {
+        Some(deadline_slot) => clock.slot >= deadline_slot || clock.unix_timestamp >= market.resolve_time,
+        None => clock.unix_timestamp >= market.bet_deadline,
+    }
+}
+
+/// Time after which a market resolving at `resolve_time` can no longer be settled and is
+/// refund-only. Fixed at creation, so later changes to `max_settle_lag` only affect new markets.
+fn refund_deadline(config: &Config, resolve_time: i64) -> Result<i64> {
+    let lag = i64::try_from(config.max_settle_lag).map_err(|_| ErrorCode::Overflow)?;
+    Ok(resolve_time.checked_add(lag).ok_or(ErrorCode::Overflow)?)
+}
+
+/// Claim deadline for a market settled at `settled_at`, or `None` when the claim window is disabled
+fn claim_deadline(config: &Config, settled_at: i64) -> Result<Option<i64>> {
+    if config.claim_window == 0 {
+        return Ok(None);
+    }
+    let window = i64::try_from(config.claim_window).map_err(|_| ErrorCode::Overflow)?;
+    Ok(Some(settled_at.checked_add(window).ok_or(ErrorCode::Overflow)?))
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[account]
+pub struct Config {
+    pub admin: Pubkey,
+    pub fee_vault: Pubkey,
+    pub create_fee: u64,
+    pub join_fee_bps: u16,        // Fee for joining/betting (basis points)
+    pub clearing_fee_bps: u16,    // Fee for claiming prize (basis points)
+    pub settle_fee_bps: u16,      // Fee for settlement (basis points)
+    pub next_market_id: u64,
+    // Everything below was appended after launch; `migrate_config` grows a legacy
+    // config and fills it in. New fields go at the end, never in between.
+    pub treasury: Pubkey,         // Protocol treasury, receives treasury_split_bps of every fee
+    pub treasury_split_bps: u16,  // Share of each collected fee routed to the treasury (basis points)
+    pub min_stake_amount: u64,    // Minimum market stake (0 = at least 1 lamport)
+    pub max_stake_amount: u64,    // Maximum market stake (0 = no cap)
+    pub claim_window: u64,        // Seconds after settlement before unclaimed winnings are forfeited (0 = never)
+    /// Maximum distinct bettors per market (0 = unlimited). Refunds are claimed
+    /// per user, so this is not bounded by any remaining_accounts limit.
+    pub max_participants: u32,
+    pub join_cutoff: u64,         // Seconds before bet_deadline when new bets stop
+    pub min_settle_fee: u64,      // Floor for the settlement fee in lamports
+    pub admin_settle_grace: u64,  // Seconds after resolve_time before the admin may settle an unproposed market
+    pub min_fee_bps: u16,         // Lower bound for creator fee overrides
+    pub max_fee_bps: u16,         // Upper bound for creator fee overrides
+    pub require_contested: bool,  // Refund instead of settling when fewer than two options have stake
+    pub refund_create_fee_on_cancel: bool, // Return the create fee to the creator on admin cancel
+    pub max_settle_lag: u64,      // Seconds after resolve_time before an unsettled market becomes refund-only
+    pub enforce_user_limits: bool, // Track per-user daily stake in UserLimits and enforce max_daily_stake
+    pub max_daily_stake: u64,     // Most a single user may stake per UTC day when limits are enforced
+    pub switch_fee_bps: u16,      // Fee for moving a bet to another option (basis points)
+    pub blacklist_enabled: bool,  // Enforce BlacklistEntry accounts in create_market and place_bet
+    pub max_markets_per_creator: u32, // Active markets a single creator may have open (0 = unlimited)
+    pub cancel_fee_credit: u64,   // Join-fee credit in lamports per bet refunded from an admin-cancelled market, capped at the bet's join fee (0 = none)
+    pub min_distinct_participants: u32, // Bettors a market needs to settle; below this it is refunded (0 = no minimum)
+    pub claim_cooldown: u64,      // Seconds a user must wait between prize claims (0 = off)
+    pub bump: u8,                 // Stored PDA bump, saves re-deriving it in every instruction
+    pub version: u8,              // Layout version (ACCOUNT_VERSION at init)
+}
+
+/// Admin-tunable settings, shared by `initialize_config` and `update_config`
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct ConfigParams {
+    pub fee_vault: Pubkey,
+    pub treasury: Pubkey,
+    pub treasury_split_bps: u16,
+    pub create_fee: u64,
+    pub join_fee_bps: u16,
+    pub clearing_fee_bps: u16,
+    pub settle_fee_bps: u16,
+    pub min_stake_amount: u64,
+    pub max_stake_amount: u64,
+    pub claim_window: u64,
+    pub max_participants: u32,
+    pub join_cutoff: u64,
+    pub min_settle_fee: u64,
+    pub admin_settle_grace: u64,
+    pub min_fee_bps: u16,
+    pub max_fee_bps: u16,
+    pub require_contested: bool,
+    pub refund_create_fee_on_cancel: bool,
+    pub max_settle_lag: u64,
+    pub enforce_user_limits: bool,
+    pub max_daily_stake: u64,
+    pub switch_fee_bps: u16,
+    pub blacklist_enabled: bool,
+    pub max_markets_per_creator: u32,
+    pub cancel_fee_credit: u64,
+    pub min_distinct_participants: u32,
+    pub claim_cooldown: u64,
+}
+
+impl ConfigParams {
+    /// Check the params before they are applied to the config at `config_key`
+    pub fn validate(&self, config_key: &Pubkey) -> Result<()> {
+        // Fee destinations must be plain accounts, never the program's own state
+        require!(self.fee_vault != Pubkey::default(), ErrorCode::InvalidFeeVault);
+        require!(self.fee_vault != *config_key, ErrorCode::InvalidFeeVault);
+        require!(self.treasury != Pubkey::default(), ErrorCode::InvalidTreasury);
+        require!(self.treasury != *config_key, ErrorCode::InvalidTreasury);
+        require!(self.treasury_split_bps <= 10000, ErrorCode::InvalidFeeBps);
+        require!(
+            self.join_fee_bps <= 10000
+                && self.clearing_fee_bps <= 10000
+                && self.settle_fee_bps <= 10000
+                && self.switch_fee_bps <= 10000,
+            ErrorCode::InvalidFeeBps
+        );
+        require!(
+            self.max_stake_amount == 0 || self.max_stake_amount >= self.min_stake_amount,
+            ErrorCode::InvalidStakeLimits
+        );
+        require!(self.min_fee_bps <= self.max_fee_bps && self.max_fee_bps <= 10000, ErrorCode::InvalidFeeBps);
+        require!(self.max_settle_lag > 0, ErrorCode::InvalidSettleLag);
+        require!(!self.enforce_user_limits || self.max_daily_stake > 0, ErrorCode::InvalidStakeLimits);
+        Ok(())
+    }
+}
+
+/// Per-market settings passed to `create_market`
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct CreateMarketParams {
+    pub challenge_window: u64,    // Duration in seconds for the challenge period
+    pub option_fee_bps: Vec<u16>, // Per-option join fee overrides (empty, or one per option; 0 = config default)
+    pub clearing_fee_bps: u16,    // Clearing fee override (0 = config default)
+    pub max_payout_multiple: u16, // Cap on a winner's gross share as a multiple of their stake (0 = uncapped)
+    pub auto_lock_ratio: u16,     // Stop new stake on an option holding more than this multiple of the rest (0 = off)
+    pub settle_authority: Option<Pubkey>, // Resolver that settles the market directly instead of bettor proposals (None = bettors propose)
+    pub bet_deadline_slot: Option<u64>, // Close betting at this slot (or resolve_time, if sooner) instead of at bet_deadline, which must then be 0 (None = by timestamp)
+}
+
+impl Config {
+    pub fn apply(&mut self, params: ConfigParams) {
+        self.fee_vault = params.fee_vault;
+        self.treasury = params.treasury;
+        self.treasury_split_bps = params.treasury_split_bps;
+        self.create_fee = params.create_fee;
+        self.join_fee_bps = params.join_fee_bps;
+        self.clearing_fee_bps = params.clearing_fee_bps;
+        self.settle_fee_bps = params.settle_fee_bps;
+        self.min_stake_amount = params.min_stake_amount;
+        self.max_stake_amount = params.max_stake_amount;
+        self.claim_window = params.claim_window;
+        self.max_participants = params.max_participants;
+        self.join_cutoff = params.join_cutoff;
+        self.min_settle_fee = params.min_settle_fee;
+        self.admin_settle_grace = params.admin_settle_grace;
+        self.min_fee_bps = params.min_fee_bps;
+        self.max_fee_bps = params.max_fee_bps;
+        self.require_contested = params.require_contested;
+        self.refund_create_fee_on_cancel = params.refund_create_fee_on_cancel;
+        self.max_settle_lag = params.max_settle_lag;
+        self.enforce_user_limits = params.enforce_user_limits;
+        self.max_daily_stake = params.max_daily_stake;
+        self.switch_fee_bps = params.switch_fee_bps;
+        self.blacklist_enabled = params.blacklist_enabled;
+        self.max_markets_per_creator = params.max_markets_per_creator;
+        self.cancel_fee_credit = params.cancel_fee_credit;
+        self.min_distinct_participants = params.min_distinct_participants;
+        self.claim_cooldown = params.claim_cooldown;
+    }
+}
+
+#[account]
+pub struct Market {
+    pub id: u64,
+    pub creator: Pubkey,
+    pub question: String,
+    pub options_count: u8,
+    pub options: [String; MAX_OPTIONS],
+    pub option_totals: [u64; MAX_OPTIONS],
+    pub option_participants: [u32; MAX_OPTIONS],
+    pub stake_amount: u64,
+    pub bet_deadline: i64,
+    pub resolve_time: i64,
+    pub challenge_window: u64,
+    pub status: u8,
+    pub proposed_outcome: Option<u8>,
+    pub proposer: Option<Pubkey>,
+    pub challenge_end_time: Option<i64>,
+    pub final_outcome: Option<u8>,
+    pub created_at: i64,
+    pub total_pool: u64,
+    // Everything below was appended after launch. Markets are allocated 1500 bytes and
+    // zero-filled, so a legacy market reads these as zero/None until `migrate_market`.
+    // New fields go at the end, never in between.
+    pub option_fee_bps: [u16; MAX_OPTIONS],
+    pub clearing_fee_bps: u16,
+    pub create_fee_paid: u64,
+    pub claim_deadline: Option<i64>,
+    pub settle_fee: u64,
+    pub total_claimed: u64,       // Sum of shares and cap refunds paid out so far
+    pub max_payout_multiple: u16, // Cap on a winner's gross share as a multiple of their stake (0 = uncapped)
+    pub bump: u8,
+    pub version: u8,              // Layout version (0 = legacy market written before versioning)
+    pub distributable: u64,       // v2: balance above rent set aside for claims at settlement, drawn down by each claim
+    pub winners_remaining: u32,   // v3: winners yet to claim; the last one absorbs the rounding dust (0 = disabled)
+    pub winners_paid: u64,        // v3: gross shares paid to winners so far
+    pub counts_toward_creator_limit: bool, // v4: still counted in the creator's CreatorStats
+    pub auto_lock_ratio: u16,     // v5: lock an option holding more than this multiple of the rest (0 = off)
+    pub frozen: bool,             // v6: admin-frozen; no new bets, settlement and claims unaffected
+    pub total_refunded: u64,      // v7: stakes returned by refund_bet, claim_cancelled_refund and close_cancelled_bet
+    pub create_fee_to_vault: u64, // v8: the fee vault's share of create_fee_paid, what an admin cancel can refund
+    pub cancelled_by_admin: bool, // v9: cancelled by admin_cancel_market rather than refunded automatically
+    pub settle_authority: Option<Pubkey>, // v10: settles the market directly in place of bettor proposals
+    pub bet_deadline_slot: Option<u64>, // v11: betting closes at this slot instead of bet_deadline
+    pub refund_deadline: i64,     // v12: unsettled markets become refund-only here (resolve_time + max_settle_lag at creation)
+}
 
 #[account]
 pub struct UserBet {
@@ -594,103 +2178,652 @@ pub struct UserBet {
     pub amount: u64,
     pub claimed: bool,
     pub bet_count: u32,
+    pub version: u8,
+    pub join_fee_paid: u64,       // v2: join fees paid for this bet after fee credit, the cap on any cancel credit
+}
+
+/// Global settlement counters, for spotting a skewed book without aggregating logs
+#[account]
+pub struct OutcomeStats {
+    pub settled_markets: u64,
+    pub outcome_counts: [u64; MAX_OPTIONS], // Settled markets by winning option index
+    pub uncontested_refunds: u64, // Markets refunded at settlement under require_contested or min_distinct_participants
+    pub settled_volume: u64,      // Sum of total_pool over settled markets
+    pub bump: u8,
+}
+
+/// Number of markets a creator has open, for `max_markets_per_creator`
+#[account]
+pub struct CreatorStats {
+    pub creator: Pubkey,
+    pub active_markets: u32,      // Created and not yet settled, cancelled or refunded
+}
+
+/// Admin-managed ban on creating markets and betting; exists only while the user is listed
+#[account]
+pub struct BlacklistEntry {
+    pub user: Pubkey,
+    pub added_at: i64,
+}
+
+/// Voluntary betting ban set by the user through `self_exclude`
+#[account]
+pub struct SelfExclusion {
+    pub user: Pubkey,
+    pub excluded_until: i64,
+}
+
+/// Per-user daily stake tracking, only used when `config.enforce_user_limits` is set
+#[account]
+pub struct UserLimits {
+    pub user: Pubkey,
+    pub day_start: i64,           // Start of the UTC day `staked_today` covers
+    pub staked_today: u64,
+}
+
+/// Join-fee credit owed to a user, granted on refunds from admin-cancelled markets
+#[account]
+pub struct FeeCredit {
+    pub user: Pubkey,
+    pub balance: u64,             // Lamports of join fees still to be waived
+}
+
+/// Per-user claim tracking, only used when `config.claim_cooldown` is set
+#[account]
+pub struct UserStats {
+    pub user: Pubkey,
+    pub last_claim_at: i64,       // When the user last claimed a prize
+}
+
+#[account]
+pub struct MarketMetadata {
+    pub market: Pubkey,
+    pub description: String,
+    pub image_uri: String,
+}
+
+/// Read-only market snapshot returned by `get_market_summary`
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct MarketSummary {
+    pub id: u64,
+    pub status: u8,
+    pub options_count: u8,
+    pub option_totals: [u64; MAX_OPTIONS],
+    pub total_pool: u64,
+    pub bet_deadline: i64,
+    pub final_outcome: Option<u8>,
+    pub bet_deadline_slot: Option<u64>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+#[repr(u8)]
+pub enum MarketStatus {
+    Open = 0,
+    Closed = 1,
+    Proposed = 2,
+    Disputed = 3,
+    Settled = 4,
+    Cancelled = 5,
+    Forfeited = 6,
+}
+
+/// Per-bet outcome reported by `scan_claims`
+#[derive(Clone, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+#[repr(u8)]
+pub enum ClaimScanStatus {
+    Claimable = 0,      // Winning bet, or a refund because nobody backed the winning option
+    Refundable = 1,     // Cancelled, unsettled past the refund deadline, or a payout cap refund
+    NotSettled = 2,
+    Lost = 3,
+    AlreadyClaimed = 4,
+    Forfeited = 5,
+}
+
+// ============================================================================
+// Account Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = CONFIG_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 8 + 8 * MAX_OPTIONS + 8 + 8 + 1,
+        seeds = [b"outcome_stats"],
+        bump
+    )]
+    pub outcome_stats: Account<'info, OutcomeStats>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitOutcomeStats<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 8 + 8 * MAX_OPTIONS + 8 + 8 + 1,
+        seeds = [b"outcome_stats"],
+        bump
+    )]
+    pub outcome_stats: Account<'info, OutcomeStats>,
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::NotAdmin)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::NotAdmin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAdmin<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::NotAdmin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeVault<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::NotAdmin)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+// Calculate Market account space:
+// 8 (discriminator) + 8 (id) + 32 (creator) + 4+256 (question string) + 1 (options_count)
+// + 10*(4+64) (options array) + 10*8 (option_totals) + 10*4 (option_participants)
+// + 8 (stake_amount) + 8 (bet_deadline) + 8 (resolve_time) + 8 (challenge_window)
+// + 1 (status) + 2 (proposed_outcome Option) + 33 (proposer Option<Pubkey>)
+// + 9 (challenge_end_time Option) + 2 (final_outcome Option) + 8 (created_at) + 8 (total_pool)
+// + 10*2 (option_fee_bps) + 2 (clearing_fee_bps) + 8 (create_fee_paid) + 9 (claim_deadline Option) + 8 (settle_fee) + 8 (total_claimed)
+// + 2 (max_payout_multiple) + 1 (bump) + 1 (version) + 8 (distributable)
+// + 4 (winners_remaining) + 8 (winners_paid) + 1 (counts_toward_creator_limit) + 2 (auto_lock_ratio)
+// + 1 (frozen) + 8 (total_refunded) + 8 (create_fee_to_vault) + 1 (cancelled_by_admin)
+// + 33 (settle_authority Option<Pubkey>) + 9 (bet_deadline_slot Option) + 8 (refund_deadline)
+// = 8 + 8 + 32 + 260 + 1 + 680 + 80 + 40 + 8 + 8 + 8 + 8 + 1 + 2 + 33 + 9 + 2 + 8 + 8 + 20 + 2 + 8 + 9 + 8 + 8 + 2 + 1 + 1 + 8 + 4 + 8 + 1 + 2 + 1 + 8 + 8 + 1 + 33 + 9 + 8 = 1346
+// Allocated with some buffer: 1500
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct BlacklistUser<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::NotAdmin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 8,
+        seeds = [b"blacklist", user.as_ref()],
+        bump
+    )]
+    pub blacklist_entry: Account<'info, BlacklistEntry>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct UnblacklistUser<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ ErrorCode::NotAdmin)]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"blacklist", user.as_ref()],
+        bump
+    )]
+    pub blacklist_entry: Account<'info, BlacklistEntry>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateMarket<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 1500, // Generous space for market data
+        seeds = [b"market", config.next_market_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = fee_vault.key() == config.fee_vault @ ErrorCode::InvalidFeeVault
+    )]
+    pub fee_vault: AccountInfo<'info>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = treasury.key() == config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    /// CHECK: May not exist; read by `check_not_excluded` only if it does
+    #[account(seeds = [b"exclusion", creator.key().as_ref()], bump)]
+    pub self_exclusion: UncheckedAccount<'info>,
+    /// CHECK: May not exist; only its presence is checked by `check_not_blacklisted`
+    #[account(seeds = [b"blacklist", creator.key().as_ref()], bump)]
+    pub blacklist_entry: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + 32 + 4,
+        seeds = [b"creator_stats", creator.key().as_ref()],
+        bump
+    )]
+    pub creator_stats: Account<'info, CreatorStats>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: u64)]
+pub struct AddMarketMetadata<'info> {
+    #[account(
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        constraint = market.id == market_id @ ErrorCode::InvalidMarketId,
+        has_one = creator @ ErrorCode::NotCreator
+    )]
+    pub market: Account<'info, Market>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + 32 + (4 + MAX_DESCRIPTION_LEN) + (4 + MAX_IMAGE_URI_LEN),
+        seeds = [b"meta", market.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, MarketMetadata>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: u64)]
+pub struct PlaceBet<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        constraint = market.id == market_id @ ErrorCode::InvalidMarketId
+    )]
+    pub market: Account<'info, Market>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = USER_BET_SPACE,
+        seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_bet: Account<'info, UserBet>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = fee_vault.key() == config.fee_vault @ ErrorCode::InvalidFeeVault
+    )]
+    pub fee_vault: AccountInfo<'info>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = treasury.key() == config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    /// Required only when `config.enforce_user_limits` is set
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + 32 + 8 + 8,
+        seeds = [b"limits", user.key().as_ref()],
+        bump
+    )]
+    pub user_limits: Option<Account<'info, UserLimits>>,
+    /// Pass to spend fee credit on the join fee
+    #[account(
+        mut,
+        seeds = [b"fee_credit", user.key().as_ref()],
+        bump
+    )]
+    pub fee_credit: Option<Account<'info, FeeCredit>>,
+    /// CHECK: May not exist; read by `check_not_excluded` only if it does
+    #[account(seeds = [b"exclusion", user.key().as_ref()], bump)]
+    pub self_exclusion: UncheckedAccount<'info>,
+    /// CHECK: May not exist; only its presence is checked by `check_not_blacklisted`
+    #[account(seeds = [b"blacklist", user.key().as_ref()], bump)]
+    pub blacklist_entry: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: u64)]
+pub struct SwitchOption<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        constraint = market.id == market_id @ ErrorCode::InvalidMarketId
+    )]
+    pub market: Account<'info, Market>,
+    #[account(
+        mut,
+        seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_bet.user == user.key() @ ErrorCode::InvalidBetOwner,
+        constraint = user_bet.market_id == market_id @ ErrorCode::InvalidMarketId
+    )]
+    pub user_bet: Account<'info, UserBet>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = fee_vault.key() == config.fee_vault @ ErrorCode::InvalidFeeVault
+    )]
+    pub fee_vault: AccountInfo<'info>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = treasury.key() == config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    /// CHECK: May not exist; read by `check_not_excluded` only if it does
+    #[account(seeds = [b"exclusion", user.key().as_ref()], bump)]
+    pub self_exclusion: UncheckedAccount<'info>,
+    /// CHECK: May not exist; only its presence is checked by `check_not_blacklisted`
+    #[account(seeds = [b"blacklist", user.key().as_ref()], bump)]
+    pub blacklist_entry: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: u64)]
+pub struct InitiateSettlement<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        constraint = market.id == market_id @ ErrorCode::InvalidMarketId
+    )]
+    pub market: Account<'info, Market>,
+    #[account(
+        seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), proposer.key().as_ref()],
+        bump,
+        constraint = user_bet.user == proposer.key() @ ErrorCode::InvalidBetOwner,
+        constraint = user_bet.market_id == market_id @ ErrorCode::InvalidMarketId
+    )]
+    pub user_bet: Account<'info, UserBet>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    pub proposer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: u64)]
+pub struct ChallengeSettlement<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        constraint = market.id == market_id @ ErrorCode::InvalidMarketId
+    )]
+    pub market: Account<'info, Market>,
+    /// Challenger must be a bettor in this market
+    #[account(
+        seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), challenger.key().as_ref()],
+        bump,
+        constraint = challenger_bet.user == challenger.key() @ ErrorCode::InvalidBetOwner,
+        constraint = challenger_bet.market_id == market_id @ ErrorCode::InvalidMarketId,
+        constraint = challenger_bet.amount > 0 @ ErrorCode::MustBeBettor
+    )]
+    pub challenger_bet: Account<'info, UserBet>,
+    pub challenger: Signer<'info>,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
-#[repr(u8)]
-pub enum MarketStatus {
-    Open = 0,
-    Closed = 1,
-    Proposed = 2,
-    Disputed = 3,
-    Settled = 4,
-    Cancelled = 5,
+#[derive(Accounts)]
+#[instruction(market_id: u64)]
+pub struct FinalizeSettlement<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        constraint = market.id == market_id @ ErrorCode::InvalidMarketId
+    )]
+    pub market: Account<'info, Market>,
+    /// Required while the market still counts toward its creator's active-market limit
+    #[account(
+        mut,
+        seeds = [b"creator_stats", market.creator.as_ref()],
+        bump
+    )]
+    pub creator_stats: Option<Account<'info, CreatorStats>>,
+    #[account(
+        seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), caller.key().as_ref()],
+        bump,
+        constraint = user_bet.user == caller.key() @ ErrorCode::InvalidBetOwner,
+        constraint = user_bet.market_id == market_id @ ErrorCode::InvalidMarketId
+    )]
+    pub user_bet: Account<'info, UserBet>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"outcome_stats"], bump = outcome_stats.bump)]
+    pub outcome_stats: Account<'info, OutcomeStats>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = fee_vault.key() == config.fee_vault @ ErrorCode::InvalidFeeVault
+    )]
+    pub fee_vault: AccountInfo<'info>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = treasury.key() == config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    pub caller: Signer<'info>,
 }
 
-// ============================================================================
-// Account Contexts
-// ============================================================================
-
 #[derive(Accounts)]
-pub struct InitializeConfig<'info> {
+#[instruction(market_id: u64)]
+pub struct ResolveDispute<'info> {
     #[account(
-        init,
-        payer = admin,
-        space = 8 + 32 + 32 + 8 + 2 + 2 + 2 + 8, // discriminator + admin + fee_vault + create_fee + 3 fee_bps + next_market_id
-        seeds = [b"config"],
+        mut,
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        constraint = market.id == market_id @ ErrorCode::InvalidMarketId
+    )]
+    pub market: Account<'info, Market>,
+    /// Required while the market still counts toward its creator's active-market limit
+    #[account(
+        mut,
+        seeds = [b"creator_stats", market.creator.as_ref()],
         bump
     )]
+    pub creator_stats: Option<Account<'info, CreatorStats>>,
+    #[account(seeds = [b"config"], bump = config.bump)]
     pub config: Account<'info, Config>,
-    #[account(mut)]
+    #[account(mut, seeds = [b"outcome_stats"], bump = outcome_stats.bump)]
+    pub outcome_stats: Account<'info, OutcomeStats>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = fee_vault.key() == config.fee_vault @ ErrorCode::InvalidFeeVault
+    )]
+    pub fee_vault: AccountInfo<'info>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = treasury.key() == config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    #[account(constraint = admin.key() == config.admin @ ErrorCode::NotAdmin)]
     pub admin: Signer<'info>,
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateConfig<'info> {
-    #[account(mut, seeds = [b"config"], bump, has_one = admin @ ErrorCode::NotAdmin)]
+#[instruction(market_id: u64)]
+pub struct AdminSettleMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        constraint = market.id == market_id @ ErrorCode::InvalidMarketId
+    )]
+    pub market: Account<'info, Market>,
+    /// Required while the market still counts toward its creator's active-market limit
+    #[account(
+        mut,
+        seeds = [b"creator_stats", market.creator.as_ref()],
+        bump
+    )]
+    pub creator_stats: Option<Account<'info, CreatorStats>>,
+    #[account(seeds = [b"config"], bump = config.bump)]
     pub config: Account<'info, Config>,
+    #[account(mut, seeds = [b"outcome_stats"], bump = outcome_stats.bump)]
+    pub outcome_stats: Account<'info, OutcomeStats>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = fee_vault.key() == config.fee_vault @ ErrorCode::InvalidFeeVault
+    )]
+    pub fee_vault: AccountInfo<'info>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = treasury.key() == config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    #[account(constraint = admin.key() == config.admin @ ErrorCode::NotAdmin)]
     pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct SetAdmin<'info> {
-    #[account(mut, seeds = [b"config"], bump, has_one = admin @ ErrorCode::NotAdmin)]
+#[instruction(market_id: u64)]
+pub struct AuthoritySettleMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        constraint = market.id == market_id @ ErrorCode::InvalidMarketId
+    )]
+    pub market: Account<'info, Market>,
+    /// Required while the market still counts toward its creator's active-market limit
+    #[account(
+        mut,
+        seeds = [b"creator_stats", market.creator.as_ref()],
+        bump
+    )]
+    pub creator_stats: Option<Account<'info, CreatorStats>>,
+    #[account(seeds = [b"config"], bump = config.bump)]
     pub config: Account<'info, Config>,
-    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"outcome_stats"], bump = outcome_stats.bump)]
+    pub outcome_stats: Account<'info, OutcomeStats>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = fee_vault.key() == config.fee_vault @ ErrorCode::InvalidFeeVault
+    )]
+    pub fee_vault: AccountInfo<'info>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = treasury.key() == config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    #[account(constraint = market.settle_authority == Some(settle_authority.key()) @ ErrorCode::NotSettleAuthority)]
+    pub settle_authority: Signer<'info>,
 }
 
-// Calculate Market account space:
-// 8 (discriminator) + 8 (id) + 32 (creator) + 4+256 (question string) + 1 (options_count)
-// + 10*(4+64) (options array) + 10*8 (option_totals) + 10*4 (option_participants)
-// + 8 (stake_amount) + 8 (bet_deadline) + 8 (resolve_time) + 8 (challenge_window)
-// + 1 (status) + 2 (proposed_outcome Option) + 33 (proposer Option<Pubkey>)
-// + 9 (challenge_end_time Option) + 2 (final_outcome Option) + 8 (created_at) + 8 (total_pool)
-// = 8 + 8 + 32 + 260 + 1 + 680 + 80 + 40 + 8 + 8 + 8 + 8 + 1 + 2 + 33 + 9 + 2 + 8 + 8 = 1196
-// Add some buffer: 1300
-
 #[derive(Accounts)]
-pub struct CreateMarket<'info> {
+#[instruction(market_id: u64)]
+pub struct ClaimPrize<'info> {
     #[account(
-        init,
-        payer = creator,
-        space = 1500, // Generous space for market data
-        seeds = [b"market", config.next_market_id.to_le_bytes().as_ref()],
-        bump
+        mut,
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        constraint = market.id == market_id @ ErrorCode::InvalidMarketId
     )]
     pub market: Account<'info, Market>,
-    #[account(mut, seeds = [b"config"], bump)]
+    #[account(
+        mut,
+        seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_bet.user == user.key() @ ErrorCode::InvalidBetOwner,
+        constraint = user_bet.market_id == market_id @ ErrorCode::InvalidMarketId
+    )]
+    pub user_bet: Account<'info, UserBet>,
+    #[account(seeds = [b"config"], bump = config.bump)]
     pub config: Account<'info, Config>,
     #[account(mut)]
-    pub creator: Signer<'info>,
+    pub user: Signer<'info>,
     /// CHECK: Validated through constraint
     #[account(
         mut,
         constraint = fee_vault.key() == config.fee_vault @ ErrorCode::InvalidFeeVault
     )]
     pub fee_vault: AccountInfo<'info>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = treasury.key() == config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    /// Required only when `config.claim_cooldown` is set
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + 32 + 8,
+        seeds = [b"user_stats", user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Option<Account<'info, UserStats>>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 #[instruction(market_id: u64)]
-pub struct PlaceBet<'info> {
+pub struct ClaimAndClose<'info> {
     #[account(
         mut,
         seeds = [b"market", market_id.to_le_bytes().as_ref()],
-        bump,
+        bump = market.bump,
         constraint = market.id == market_id @ ErrorCode::InvalidMarketId
     )]
     pub market: Account<'info, Market>,
     #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + 8 + 32 + 1 + 8 + 1 + 4,
+        mut,
+        close = user,
         seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), user.key().as_ref()],
-        bump
+        bump,
+        constraint = user_bet.user == user.key() @ ErrorCode::InvalidBetOwner,
+        constraint = user_bet.market_id == market_id @ ErrorCode::InvalidMarketId
     )]
     pub user_bet: Account<'info, UserBet>,
-    #[account(seeds = [b"config"], bump)]
+    #[account(seeds = [b"config"], bump = config.bump)]
     pub config: Account<'info, Config>,
     #[account(mut)]
     pub user: Signer<'info>,
@@ -700,94 +2833,182 @@ pub struct PlaceBet<'info> {
         constraint = fee_vault.key() == config.fee_vault @ ErrorCode::InvalidFeeVault
     )]
     pub fee_vault: AccountInfo<'info>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = treasury.key() == config.treasury @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    /// Required only when `config.claim_cooldown` is set
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + 32 + 8,
+        seeds = [b"user_stats", user.key().as_ref()],
+        bump
+    )]
+    pub user_stats: Option<Account<'info, UserStats>>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 #[instruction(market_id: u64)]
-pub struct InitiateSettlement<'info> {
+pub struct RefundBet<'info> {
     #[account(
         mut,
         seeds = [b"market", market_id.to_le_bytes().as_ref()],
-        bump,
+        bump = market.bump,
         constraint = market.id == market_id @ ErrorCode::InvalidMarketId
     )]
     pub market: Account<'info, Market>,
+    /// Required while the market still counts toward its creator's active-market limit
     #[account(
-        seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), proposer.key().as_ref()],
+        mut,
+        seeds = [b"creator_stats", market.creator.as_ref()],
+        bump
+    )]
+    pub creator_stats: Option<Account<'info, CreatorStats>>,
+    #[account(
+        mut,
+        seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), user.key().as_ref()],
         bump,
-        constraint = user_bet.user == proposer.key() @ ErrorCode::InvalidBetOwner,
+        constraint = user_bet.user == user.key() @ ErrorCode::InvalidBetOwner,
         constraint = user_bet.market_id == market_id @ ErrorCode::InvalidMarketId
     )]
     pub user_bet: Account<'info, UserBet>,
-    pub proposer: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub user: Signer<'info>,
 }
 
 #[derive(Accounts)]
 #[instruction(market_id: u64)]
-pub struct ChallengeSettlement<'info> {
+pub struct FreezeMarket<'info> {
     #[account(
         mut,
         seeds = [b"market", market_id.to_le_bytes().as_ref()],
-        bump,
+        bump = market.bump,
         constraint = market.id == market_id @ ErrorCode::InvalidMarketId
     )]
     pub market: Account<'info, Market>,
-    /// Challenger must be a bettor in this market
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(constraint = admin.key() == config.admin @ ErrorCode::NotAdmin)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: u64)]
+pub struct MigrateMarket<'info> {
     #[account(
-        seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), challenger.key().as_ref()],
-        bump,
-        constraint = challenger_bet.user == challenger.key() @ ErrorCode::InvalidBetOwner,
-        constraint = challenger_bet.market_id == market_id @ ErrorCode::InvalidMarketId,
-        constraint = challenger_bet.amount > 0 @ ErrorCode::MustBeBettor
+        mut,
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump, // re-derived: legacy markets have no stored bump
+        constraint = market.id == market_id @ ErrorCode::InvalidMarketId
     )]
-    pub challenger_bet: Account<'info, UserBet>,
-    pub challenger: Signer<'info>,
+    pub market: Account<'info, Market>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(constraint = admin.key() == config.admin @ ErrorCode::NotAdmin)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    /// CHECK: may still be in the legacy layout; owner, discriminator and admin are checked
+    /// by hand before it is grown and deserialized
+    #[account(mut, seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: u64, user: Pubkey)]
+pub struct MigrateUserBet<'info> {
+    /// CHECK: may still be in the legacy layout; owner is checked and it is deserialized
+    /// after being grown
+    #[account(mut, seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), user.as_ref()], bump)]
+    pub user_bet: UncheckedAccount<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(mut, constraint = admin.key() == config.admin @ ErrorCode::NotAdmin)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: u64)]
+pub struct AdminCancelMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        constraint = market.id == market_id @ ErrorCode::InvalidMarketId
+    )]
+    pub market: Account<'info, Market>,
+    /// Required while the market still counts toward its creator's active-market limit
+    #[account(
+        mut,
+        seeds = [b"creator_stats", market.creator.as_ref()],
+        bump
+    )]
+    pub creator_stats: Option<Account<'info, CreatorStats>>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(constraint = admin.key() == config.admin @ ErrorCode::NotAdmin)]
+    pub admin: Signer<'info>,
+    /// Only needed (as a co-signer) to refund the create fee
+    #[account(
+        mut,
+        constraint = fee_vault.key() == config.fee_vault @ ErrorCode::InvalidFeeVault
+    )]
+    pub fee_vault: Option<Signer<'info>>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = creator.key() == market.creator @ ErrorCode::NotCreator
+    )]
+    pub creator: Option<AccountInfo<'info>>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 #[instruction(market_id: u64)]
-pub struct FinalizeSettlement<'info> {
+pub struct CloseEmptyMarket<'info> {
     #[account(
         mut,
+        close = creator,
         seeds = [b"market", market_id.to_le_bytes().as_ref()],
-        bump,
+        bump = market.bump,
         constraint = market.id == market_id @ ErrorCode::InvalidMarketId
     )]
     pub market: Account<'info, Market>,
+    /// Required while the market still counts toward its creator's active-market limit
     #[account(
-        seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), caller.key().as_ref()],
-        bump,
-        constraint = user_bet.user == caller.key() @ ErrorCode::InvalidBetOwner,
-        constraint = user_bet.market_id == market_id @ ErrorCode::InvalidMarketId
+        mut,
+        seeds = [b"creator_stats", market.creator.as_ref()],
+        bump
     )]
-    pub user_bet: Account<'info, UserBet>,
-    pub caller: Signer<'info>,
-}
-
-#[derive(Accounts)]
-#[instruction(market_id: u64)]
-pub struct ResolveDispute<'info> {
+    pub creator_stats: Option<Account<'info, CreatorStats>>,
+    /// CHECK: Only receives the market's rent; validated through constraint
     #[account(
         mut,
-        seeds = [b"market", market_id.to_le_bytes().as_ref()],
-        bump,
-        constraint = market.id == market_id @ ErrorCode::InvalidMarketId
+        constraint = creator.key() == market.creator @ ErrorCode::NotCreator
     )]
-    pub market: Account<'info, Market>,
-    #[account(seeds = [b"config"], bump)]
-    pub config: Account<'info, Config>,
-    #[account(constraint = admin.key() == config.admin @ ErrorCode::NotAdmin)]
-    pub admin: Signer<'info>,
+    pub creator: AccountInfo<'info>,
+    pub caller: Signer<'info>,
 }
 
 #[derive(Accounts)]
 #[instruction(market_id: u64)]
-pub struct ClaimPrize<'info> {
+pub struct ClaimCancelledRefund<'info> {
     #[account(
         mut,
         seeds = [b"market", market_id.to_le_bytes().as_ref()],
-        bump,
+        bump = market.bump,
         constraint = market.id == market_id @ ErrorCode::InvalidMarketId
     )]
     pub market: Account<'info, Market>,
@@ -799,76 +3020,117 @@ pub struct ClaimPrize<'info> {
         constraint = user_bet.market_id == market_id @ ErrorCode::InvalidMarketId
     )]
     pub user_bet: Account<'info, UserBet>,
-    #[account(seeds = [b"config"], bump)]
+    #[account(seeds = [b"config"], bump = config.bump)]
     pub config: Account<'info, Config>,
     #[account(mut)]
     pub user: Signer<'info>,
-    /// CHECK: Validated through constraint
+    /// Only credited when `config.cancel_fee_credit` is set and the admin cancelled the market
     #[account(
-        mut,
-        constraint = fee_vault.key() == config.fee_vault @ ErrorCode::InvalidFeeVault
+        init_if_needed,
+        payer = user,
+        space = 8 + 32 + 8,
+        seeds = [b"fee_credit", user.key().as_ref()],
+        bump
     )]
-    pub fee_vault: AccountInfo<'info>,
+    pub fee_credit: Option<Account<'info, FeeCredit>>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 #[instruction(market_id: u64)]
-pub struct RefundBet<'info> {
+pub struct CloseCancelledBet<'info> {
     #[account(
         mut,
         seeds = [b"market", market_id.to_le_bytes().as_ref()],
-        bump,
+        bump = market.bump,
         constraint = market.id == market_id @ ErrorCode::InvalidMarketId
     )]
     pub market: Account<'info, Market>,
     #[account(
         mut,
+        close = user,
         seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), user.key().as_ref()],
         bump,
         constraint = user_bet.user == user.key() @ ErrorCode::InvalidBetOwner,
         constraint = user_bet.market_id == market_id @ ErrorCode::InvalidMarketId
     )]
     pub user_bet: Account<'info, UserBet>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    #[account(constraint = admin.key() == config.admin @ ErrorCode::NotAdmin)]
+    pub admin: Signer<'info>,
+    /// CHECK: Only receives the refund and rent; tied to the bet by its seeds
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub user: AccountInfo<'info>,
+    /// Credited like in `claim_cancelled_refund`; the user isn't signing, so it must already exist
+    #[account(mut, seeds = [b"fee_credit", user.key().as_ref()], bump)]
+    pub fee_credit: Option<Account<'info, FeeCredit>>,
 }
 
 #[derive(Accounts)]
 #[instruction(market_id: u64)]
-pub struct AdminCancelMarket<'info> {
+pub struct ForfeitUnclaimed<'info> {
     #[account(
         mut,
         seeds = [b"market", market_id.to_le_bytes().as_ref()],
-        bump,
+        bump = market.bump,
         constraint = market.id == market_id @ ErrorCode::InvalidMarketId
     )]
     pub market: Account<'info, Market>,
-    #[account(seeds = [b"config"], bump)]
+    #[account(seeds = [b"config"], bump = config.bump)]
     pub config: Account<'info, Config>,
-    #[account(constraint = admin.key() == config.admin @ ErrorCode::NotAdmin)]
-    pub admin: Signer<'info>,
+    /// CHECK: Validated through constraint
+    #[account(
+        mut,
+        constraint = fee_vault.key() == config.fee_vault @ ErrorCode::InvalidFeeVault
+    )]
+    pub fee_vault: AccountInfo<'info>,
+    pub caller: Signer<'info>,
 }
 
 #[derive(Accounts)]
 #[instruction(market_id: u64)]
-pub struct ClaimCancelledRefund<'info> {
+pub struct GetMarketSummary<'info> {
     #[account(
-        mut,
         seeds = [b"market", market_id.to_le_bytes().as_ref()],
-        bump,
+        bump = market.bump,
         constraint = market.id == market_id @ ErrorCode::InvalidMarketId
     )]
     pub market: Account<'info, Market>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_id: u64)]
+pub struct CheckWinner<'info> {
     #[account(
-        mut,
-        seeds = [b"user_bet", market_id.to_le_bytes().as_ref(), user.key().as_ref()],
-        bump,
-        constraint = user_bet.user == user.key() @ ErrorCode::InvalidBetOwner,
-        constraint = user_bet.market_id == market_id @ ErrorCode::InvalidMarketId
+        seeds = [b"market", market_id.to_le_bytes().as_ref()],
+        bump = market.bump,
+        constraint = market.id == market_id @ ErrorCode::InvalidMarketId
     )]
+    pub market: Account<'info, Market>,
+    #[account(constraint = user_bet.market_id == market_id @ ErrorCode::InvalidMarketId)]
     pub user_bet: Account<'info, UserBet>,
+}
+
+#[derive(Accounts)]
+pub struct SelfExclude<'info> {
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + 32 + 8,
+        seeds = [b"exclusion", user.key().as_ref()],
+        bump
+    )]
+    pub self_exclusion: Account<'info, SelfExclusion>,
     #[account(mut)]
     pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ScanClaims<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
 }
 
 // ============================================================================
@@ -888,6 +3150,14 @@ pub struct MarketCreated {
     pub challenge_window: u64,
 }
 
+#[event]
+pub struct MarketMetadataSet {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub description: String,
+    pub image_uri: String,
+}
+
 #[event]
 pub struct BetPlaced {
     pub market: Pubkey,
@@ -896,6 +3166,18 @@ pub struct BetPlaced {
     pub option_index: u8,
     pub amount: u64,
     pub total_user_amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct OptionSwitched {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub user: Pubkey,
+    pub from_option: u8,
+    pub to_option: u8,
+    pub amount: u64,
+    pub fee: u64,
 }
 
 #[event]
@@ -921,6 +3203,31 @@ pub struct MarketSettled {
     pub outcome: u8,
     pub settled_by: Pubkey,
     pub is_admin_resolution: bool,
+    pub settle_fee: u64,
+}
+
+#[event]
+pub struct MarketSettledByAdmin {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub outcome: u8,
+    pub admin: Pubkey,
+    pub settle_fee: u64,
+}
+
+#[event]
+pub struct SettleFeeShortfall {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub expected_fee: u64,
+    pub charged_fee: u64,
+}
+
+#[event]
+pub struct UncontestedMarketRefunded {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub total_pool: u64,
 }
 
 #[event]
@@ -930,6 +3237,15 @@ pub struct PrizeClaimed {
     pub user: Pubkey,
     pub amount: u64,
     pub fee: u64,
+    // amount + fee = winners_pool * user_bet_amount / winning_pool, rounded down, except for
+    // the last winner, who takes what is left of winners_pool. winners_pool is
+    // total_pool - settle_fee, capped at max_payout_multiple * winning_pool.
+    pub user_bet_amount: u64,
+    pub winning_pool: u64,
+    pub total_pool: u64,
+    pub settle_fee: u64,
+    pub winners_pool: u64,
+    pub last_winner: bool,
 }
 
 #[event]
@@ -940,11 +3256,47 @@ pub struct BetRefunded {
     pub amount: u64,
 }
 
+#[event]
+pub struct FeeCreditGranted {
+    pub user: Pubkey,
+    pub market_id: u64,
+    pub amount: u64,
+    pub balance: u64,
+}
+
+#[event]
+pub struct UserBetClosed {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub user: Pubkey,
+    pub refunded: u64,
+    pub admin: Pubkey,
+}
+
+/// Warning: a settled market's winning option has no stake, so bettors are refunded
+#[event]
+pub struct WinningOptionEmpty {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub winning_option: u8,
+    pub user: Pubkey,
+    pub refunded: u64,
+}
+
 #[event]
 pub struct MarketCancelled {
     pub market: Pubkey,
     pub market_id: u64,
     pub admin: Pubkey,
+    pub create_fee_refunded: u64,
+}
+
+#[event]
+pub struct EmptyMarketClosed {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub creator: Pubkey,
+    pub closed_by: Pubkey,
 }
 
 #[event]
@@ -953,6 +3305,65 @@ pub struct AdminChanged {
     pub new_admin: Pubkey,
 }
 
+#[event]
+pub struct WinningsForfeited {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub amount: u64,
+    pub caller: Pubkey,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub market_id: u64,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct MarketFrozenUpdated {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub frozen: bool,
+}
+
+#[event]
+pub struct UserBlacklistUpdated {
+    pub user: Pubkey,
+    pub blacklisted: bool,
+}
+
+#[event]
+pub struct SelfExclusionSet {
+    pub user: Pubkey,
+    pub excluded_until: i64,
+}
+
+#[event]
+pub struct PayoutCapRefunded {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ClaimScanResult {
+    pub market: Pubkey,
+    pub market_id: u64,
+    pub user: Pubkey,
+    pub status: u8,     // ClaimScanStatus
+    pub amount: u64,    // Net lamports the user would receive (0 unless claimable/refundable)
+}
+
+#[event]
+pub struct ConfigFieldUpdated {
+    pub field: String,
+    pub old_value: Pubkey,
+    pub new_value: Pubkey,
+}
+
 // ============================================================================
 // Error Codes
 // ============================================================================
@@ -1023,8 +3434,406 @@ pub enum ErrorCode {
     InvalidFeeVault,
     #[msg("Invalid bet owner")]
     InvalidBetOwner,
-    #[msg("Stake amount too small (minimum 0.001 SOL)")]
+    #[msg("Stake amount below configured minimum")]
     StakeTooSmall,
     #[msg("Invalid admin address")]
     InvalidAdmin,
+    #[msg("Stake amount above configured maximum")]
+    StakeTooLarge,
+    #[msg("Maximum stake must be zero or at least the minimum stake")]
+    InvalidStakeLimits,
+    #[msg("Settlement lag must be positive")]
+    InvalidSettleLag,
+    #[msg("Market has no claim deadline")]
+    NoClaimDeadline,
+    #[msg("Claim window has not ended")]
+    ClaimWindowNotEnded,
+    #[msg("Market has reached its participant limit")]
+    MarketFull,
+    #[msg("Description too long")]
+    DescriptionTooLong,
+    #[msg("Image URI too long")]
+    ImageUriTooLong,
+    #[msg("Not market creator")]
+    NotCreator,
+    #[msg("Join window has closed")]
+    JoinWindowClosed,
+    #[msg("Fee basis points must not exceed 10000")]
+    InvalidFeeBps,
+    #[msg("Admin settlement grace period has not passed")]
+    AdminSettleTooEarly,
+    #[msg("Fee override outside admin-set bounds")]
+    FeeOutOfBounds,
+    #[msg("Refund window is already open")]
+    RefundWindowOpen,
+    #[msg("Expected (market, user_bet) account pairs")]
+    InvalidScanAccounts,
+    #[msg("UserLimits account is required while user limits are enforced")]
+    UserLimitsRequired,
+    #[msg("Daily stake limit exceeded")]
+    DailyLimitExceeded,
+    #[msg("User is self-excluded")]
+    SelfExcluded,
+    #[msg("Self-exclusion cannot be shortened")]
+    ExclusionCannotBeShortened,
+    #[msg("Creator has too many active markets")]
+    TooManyActiveMarkets,
+    #[msg("CreatorStats account is required for this market")]
+    CreatorStatsRequired,
+    #[msg("User is blacklisted")]
+    UserBlacklisted,
+    #[msg("Market balance can't cover the protocol fee")]
+    InsufficientForFee,
+    #[msg("Bet is already on this option")]
+    SameOption,
+    #[msg("Invalid treasury")]
+    InvalidTreasury,
+    #[msg("Account is already at the current version")]
+    AccountUpToDate,
+    #[msg("No migration from this account version")]
+    UnknownAccountVersion,
+    #[msg("Market holds less than it owes")]
+    InsolventMarket,
+    #[msg("Option is locked: it outweighs the rest of the market")]
+    AutoLocked,
+    #[msg("Market is frozen")]
+    MarketFrozen,
+    #[msg("UserStats account is required while a claim cooldown is set")]
+    UserStatsRequired,
+    #[msg("Claim cooldown has not passed")]
+    ClaimTooSoon,
+    #[msg("Account is not owned by this program")]
+    InvalidAccountOwner,
+    #[msg("Market has bets")]
+    MarketNotEmpty,
+    #[msg("Market has not reached its resolve time")]
+    ResolveTimeNotReached,
+    #[msg("Invalid settle authority")]
+    InvalidSettleAuthority,
+    #[msg("Market is settled by its settle authority")]
+    SettleAuthorityOnly,
+    #[msg("Not the market's settle authority")]
+    NotSettleAuthority,
+    #[msg("Betting is still open")]
+    BettingStillOpen,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+
+    /// Market layout as written before versioning; every later field is appended after it
+    #[derive(AnchorSerialize)]
+    struct LegacyMarket {
+        id: u64,
+        creator: Pubkey,
+        question: String,
+        options_count: u8,
+        options: [String; MAX_OPTIONS],
+        option_totals: [u64; MAX_OPTIONS],
+        option_participants: [u32; MAX_OPTIONS],
+        stake_amount: u64,
+        bet_deadline: i64,
+        resolve_time: i64,
+        challenge_window: u64,
+        status: u8,
+        proposed_outcome: Option<u8>,
+        proposer: Option<Pubkey>,
+        challenge_end_time: Option<i64>,
+        final_outcome: Option<u8>,
+        created_at: i64,
+        total_pool: u64,
+    }
+
+    /// Serialize a legacy market into a zero-filled 1500-byte account, as create_market allocated it
+    fn legacy_market_bytes(status: MarketStatus) -> Vec<u8> {
+        let legacy = LegacyMarket {
+            id: 7,
+            creator: Pubkey::new_unique(),
+            question: "Will it rain?".to_string(),
+            options_count: 2,
+            options: core::array::from_fn(|i| if i < 2 { format!("opt{i}") } else { String::new() }),
+            option_totals: core::array::from_fn(|i| if i < 2 { 1_000 } else { 0 }),
+            option_participants: core::array::from_fn(|i| if i < 2 { 1 } else { 0 }),
+            stake_amount: 1_000,
+            bet_deadline: 100,
+            resolve_time: 200,
+            challenge_window: 60,
+            status: status as u8,
+            proposed_outcome: Some(1),
+            proposer: Some(Pubkey::new_unique()),
+            challenge_end_time: Some(260),
+            final_outcome: Some(1),
+            created_at: 50,
+            total_pool: 2_000,
+        };
+        let mut data = vec![0u8; 1500];
+        data[..8].copy_from_slice(&Market::DISCRIMINATOR);
+        legacy.serialize(&mut &mut data[8..]).unwrap();
+        data
+    }
+
+    fn legacy_market(status: MarketStatus) -> Market {
+        Market::try_deserialize(&mut &legacy_market_bytes(status)[..]).unwrap()
+    }
+
+    #[test]
+    fn legacy_market_reads_appended_fields_as_zero() {
+        let market = legacy_market(MarketStatus::Settled);
+
+        assert_eq!(market.id, 7);
+        assert_eq!(market.question, "Will it rain?");
+        assert_eq!(market.total_pool, 2_000);
+        assert_eq!(market.final_outcome, Some(1));
+        assert_eq!(market.option_fee_bps, [0; MAX_OPTIONS]);
+        assert_eq!(market.claim_deadline, None);
+        assert_eq!(market.bump, 0);
+        assert_eq!(market.version, 0);
+    }
+
+    #[test]
+    fn legacy_market_migrates_to_current() {
+        let mut market = legacy_market(MarketStatus::Settled);
+
+        upgrade_market(&mut market, &zeroed_config(), 254, 1_900).unwrap();
+
+        assert_eq!(market.version, MARKET_VERSION);
+        assert_eq!(market.bump, 254);
+        assert_eq!(market.distributable, 1_900);
+        assert_eq!(market.total_pool, 2_000);
+    }
+
+    #[test]
+    fn v1_market_migrates_through_v2() {
+        let mut market = legacy_market(MarketStatus::Settled);
+        market.bump = 253;
+        market.version = 1;
+
+        upgrade_market(&mut market, &zeroed_config(), 0, 1_500).unwrap();
+
+        assert_eq!(market.version, MARKET_VERSION);
+        // v1 markets already stored their bump
+        assert_eq!(market.bump, 253);
+        // v1 -> v2 snapshots the remaining balance of a settled market
+        assert_eq!(market.distributable, 1_500);
+    }
+
+    #[test]
+    fn v1_unsettled_market_snapshots_nothing() {
+        let mut market = legacy_market(MarketStatus::Open);
+        market.version = 1;
+
+        upgrade_market(&mut market, &zeroed_config(), 0, 1_500).unwrap();
+
+        assert_eq!(market.version, MARKET_VERSION);
+        assert_eq!(market.distributable, 0);
+    }
+
+    #[test]
+    fn v11_market_fixes_refund_deadline_at_current_lag() {
+        let mut market = legacy_market(MarketStatus::Open);
+        market.version = 11;
+        let mut config = zeroed_config();
+        config.max_settle_lag = 50;
+
+        upgrade_market(&mut market, &config, 0, 0).unwrap();
+
+        assert_eq!(market.version, MARKET_VERSION);
+        assert_eq!(market.refund_deadline, 250);
+    }
+
+    #[test]
+    fn current_market_is_up_to_date() {
+        let mut market = legacy_market(MarketStatus::Open);
+        market.version = MARKET_VERSION;
+
+        let err = upgrade_market(&mut market, &zeroed_config(), 0, 0).unwrap_err();
+        assert_eq!(err, ErrorCode::AccountUpToDate.into());
+    }
+
+    #[test]
+    fn daily_stake_resets_at_utc_day_boundary() {
+        let mut config = zeroed_config();
+        config.max_daily_stake = 100;
+        let mut limits = UserLimits { user: Pubkey::default(), day_start: 0, staked_today: 0 };
+        let day_end = 20_000 * SECONDS_PER_DAY;
+
+        record_daily_stake(&mut limits, &config, 100, day_end - 1).unwrap();
+        assert_eq!(limits.day_start, day_end - SECONDS_PER_DAY);
+        assert_eq!(limits.staked_today, 100);
+        assert_eq!(
+            record_daily_stake(&mut limits, &config, 1, day_end - 1).unwrap_err(),
+            ErrorCode::DailyLimitExceeded.into()
+        );
+
+        // The next UTC day starts with a fresh allowance
+        record_daily_stake(&mut limits, &config, 100, day_end).unwrap();
+        assert_eq!(limits.day_start, day_end);
+        assert_eq!(limits.staked_today, 100);
+    }
+
+    #[test]
+    fn cancel_credit_needs_an_admin_cancel() {
+        assert_eq!(cancel_credit(200_000, false, 500_000), 0);
+        assert_eq!(cancel_credit(200_000, true, 500_000), 200_000);
+    }
+
+    #[test]
+    fn cancel_credit_is_capped_at_the_join_fee() {
+        assert_eq!(cancel_credit(200_000, true, 50_000), 50_000);
+        // bets placed entirely on credit, or before the fee was recorded, earn nothing
+        assert_eq!(cancel_credit(200_000, true, 0), 0);
+    }
+
+    const RESERVE: u64 = 1_000_000;
+
+    #[test]
+    fn payable_below_reserve_pays_nothing() {
+        assert!(check_payable(RESERVE - 1, RESERVE, 0).is_ok());
+        assert_eq!(check_payable(RESERVE - 1, RESERVE, 1).unwrap_err(), ErrorCode::InsufficientFunds.into());
+    }
+
+    #[test]
+    fn payable_at_reserve_pays_nothing() {
+        assert!(check_payable(RESERVE, RESERVE, 0).is_ok());
+        assert_eq!(check_payable(RESERVE, RESERVE, 1).unwrap_err(), ErrorCode::InsufficientFunds.into());
+    }
+
+    #[test]
+    fn payable_above_reserve_pays_the_excess() {
+        assert!(check_payable(RESERVE + 1, RESERVE, 1).is_ok());
+        assert_eq!(check_payable(RESERVE + 1, RESERVE, 2).unwrap_err(), ErrorCode::InsufficientFunds.into());
+    }
+
+    #[test]
+    fn fee_and_payout_shortfalls_are_distinct() {
+        assert_eq!(check_fee_payable(RESERVE + 1, RESERVE, 2).unwrap_err(), ErrorCode::InsufficientForFee.into());
+        assert_eq!(check_payable(RESERVE + 1, RESERVE, 2).unwrap_err(), ErrorCode::InsufficientFunds.into());
+        assert!(check_fee_payable(RESERVE + 2, RESERVE, 2).is_ok());
+    }
+
+    /// Config with every setting zeroed
+    fn zeroed_config() -> Config {
+        let mut data = vec![0u8; CONFIG_SPACE];
+        data[..8].copy_from_slice(&Config::DISCRIMINATOR);
+        Config::try_deserialize(&mut &data[..]).unwrap()
+    }
+
+    /// Config with only the settle fee settings that `settle_fee_due` reads
+    fn settle_fee_config(settle_fee_bps: u16, min_settle_fee: u64) -> Config {
+        let mut config = zeroed_config();
+        config.settle_fee_bps = settle_fee_bps;
+        config.min_settle_fee = min_settle_fee;
+        config
+    }
+
+    #[test]
+    fn settle_fee_is_uncapped_when_the_balance_covers_it() {
+        let config = settle_fee_config(200, 0);
+        assert_eq!(settle_fee_due(1_000_000, &config, 1_000_000).unwrap(), (20_000, 20_000));
+    }
+
+    #[test]
+    fn settle_fee_is_capped_when_accounting_exceeds_the_balance() {
+        // total_pool says 1_000_000 but only 5_000 is left above rent
+        let config = settle_fee_config(200, 0);
+        assert_eq!(settle_fee_due(1_000_000, &config, 5_000).unwrap(), (20_000, 5_000));
+        assert_eq!(settle_fee_due(1_000_000, &config, 0).unwrap(), (20_000, 0));
+    }
+
+    #[test]
+    fn settle_fee_floor_never_exceeds_the_pool() {
+        let config = settle_fee_config(200, 50_000);
+        assert_eq!(settle_fee_due(1_000_000, &config, 1_000_000).unwrap(), (50_000, 50_000));
+        assert_eq!(settle_fee_due(10_000, &config, 10_000).unwrap(), (10_000, 10_000));
+    }
+
+    fn clock_at(slot: u64, unix_timestamp: i64) -> Clock {
+        Clock { slot, unix_timestamp, ..Clock::default() }
+    }
+
+    #[test]
+    fn slot_market_closes_betting_by_slot() {
+        let config = zeroed_config();
+        let mut market = legacy_market(MarketStatus::Open);
+        market.bet_deadline = 0;
+        market.bet_deadline_slot = Some(1_000);
+
+        // The timestamp is ignored for slot-based markets until resolve_time (200)
+        assert!(check_betting_open(&market, &config, &clock_at(999, 199)).is_ok());
+        assert!(!betting_closed(&market, &clock_at(999, 199)));
+        assert_eq!(
+            check_betting_open(&market, &config, &clock_at(1_000, 0)).unwrap_err(),
+            ErrorCode::BettingClosed.into()
+        );
+        assert!(betting_closed(&market, &clock_at(1_000, 0)));
+    }
+
+    #[test]
+    fn slot_market_closes_betting_at_resolve_time() {
+        let config = zeroed_config();
+        let mut market = legacy_market(MarketStatus::Open); // resolve_time 200
+        market.bet_deadline = 0;
+        market.bet_deadline_slot = Some(1_000);
+
+        // A slot that lags wall-clock time can't keep betting open past resolution
+        assert_eq!(
+            check_betting_open(&market, &config, &clock_at(999, 200)).unwrap_err(),
+            ErrorCode::BettingClosed.into()
+        );
+        assert!(betting_closed(&market, &clock_at(999, 200)));
+    }
+
+    #[test]
+    fn slot_market_join_cutoff_is_converted_to_slots() {
+        // 4 seconds at 400ms per slot stops new bets 10 slots early
+        let mut config = zeroed_config();
+        config.join_cutoff = 4;
+        let mut market = legacy_market(MarketStatus::Open);
+        market.bet_deadline_slot = Some(1_000);
+
+        assert!(check_betting_open(&market, &config, &clock_at(989, 0)).is_ok());
+        assert_eq!(
+            check_betting_open(&market, &config, &clock_at(990, 0)).unwrap_err(),
+            ErrorCode::JoinWindowClosed.into()
+        );
+    }
+
+    #[test]
+    fn timestamp_market_closes_betting_by_timestamp() {
+        let config = zeroed_config();
+        let market = legacy_market(MarketStatus::Open); // bet_deadline 100
+
+        assert!(check_betting_open(&market, &config, &clock_at(u64::MAX, 99)).is_ok());
+        assert_eq!(
+            check_betting_open(&market, &config, &clock_at(0, 100)).unwrap_err(),
+            ErrorCode::BettingClosed.into()
+        );
+        assert!(betting_closed(&market, &clock_at(0, 100)));
+    }
+
+    #[test]
+    fn legacy_config_reads_after_growing() {
+        let admin = Pubkey::new_unique();
+        let fee_vault = Pubkey::new_unique();
+        // discriminator + admin + fee_vault + create_fee + 3 fee_bps + next_market_id
+        let mut data = Vec::with_capacity(CONFIG_SPACE);
+        data.extend_from_slice(&Config::DISCRIMINATOR);
+        (admin, fee_vault, 10u64, 100u16, 200u16, 300u16, 42u64)
+            .serialize(&mut data)
+            .unwrap();
+        assert_eq!(data.len(), 8 + 32 + 32 + 8 + 2 + 2 + 2 + 8);
+
+        assert!(Config::try_deserialize(&mut &data[..]).is_err());
+
+        // migrate_config zero-fills the grown tail
+        data.resize(CONFIG_SPACE, 0);
+        let config = Config::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(config.admin, admin);
+        assert_eq!(config.fee_vault, fee_vault);
+        assert_eq!(config.settle_fee_bps, 300);
+        assert_eq!(config.next_market_id, 42);
+        assert_eq!(config.version, 0);
+    }
 }
@@ -20,6 +20,9 @@ describe("Social Bet - PRD Acceptance Tests", () => {
   const user2 = Keypair.generate();
   const user3 = Keypair.generate();
   const feeVault = Keypair.generate();
+  const treasury = Keypair.generate();
+  // Takes over as admin in the "Admin Management" suite
+  const newAdmin = Keypair.generate();
 
   let configPda: PublicKey;
 
@@ -45,6 +48,164 @@ describe("Social Bet - PRD Acceptance Tests", () => {
     )[0];
   };
 
+  const getCreatorStatsPda = (creatorKey: PublicKey) => {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("creator_stats"), creatorKey.toBuffer()],
+      program.programId
+    )[0];
+  };
+
+  const getOutcomeStatsPda = () => {
+    return PublicKey.findProgramAddressSync([Buffer.from("outcome_stats")], program.programId)[0];
+  };
+
+  const initialConfigParams = {
+    feeVault: feeVault.publicKey,
+    treasury: treasury.publicKey,
+    treasurySplitBps: 0,               // everything to the fee vault
+    createFee: new anchor.BN(5000000), // 0.005 SOL
+    joinFeeBps: 50,                    // 0.5%
+    clearingFeeBps: 100,               // 1%
+    settleFeeBps: 200,                 // 2%
+    minStakeAmount: new anchor.BN(1000000), // 0.001 SOL
+    maxStakeAmount: new anchor.BN(0),  // uncapped
+    claimWindow: new anchor.BN(0),     // never forfeit
+    maxParticipants: 0,                // unlimited
+    joinCutoff: new anchor.BN(0),      // bets allowed until the deadline
+    minSettleFee: new anchor.BN(0),    // bps only
+    adminSettleGrace: new anchor.BN(86400), // 1 day after resolve time
+    minFeeBps: 0,
+    maxFeeBps: 10000,
+    requireContested: false,
+    refundCreateFeeOnCancel: false,
+    maxSettleLag: new anchor.BN(7 * 24 * 3600), // refund-only 7 days after resolve time
+    enforceUserLimits: false,
+    maxDailyStake: new anchor.BN(0),
+    switchFeeBps: 100,                 // 1% to move a bet to another option
+    blacklistEnabled: false,
+    maxMarketsPerCreator: 0,           // unlimited
+    cancelFeeCredit: new anchor.BN(0), // no goodwill credit on cancel
+    minDistinctParticipants: 0,        // no minimum to settle
+    claimCooldown: new anchor.BN(0),   // claims not rate-limited
+  };
+
+  // create_market params with every optional override left at its default
+  const marketParams = (challengeWindow: number, overrides: Record<string, any> = {}) => ({
+    challengeWindow: new anchor.BN(challengeWindow),
+    optionFeeBps: [] as number[],
+    clearingFeeBps: 0,
+    maxPayoutMultiple: 0,
+    autoLockRatio: 0,
+    settleAuthority: null,
+    betDeadlineSlot: null,
+    ...overrides,
+  });
+
+  // Re-submit the current config with only the given fields changed
+  const updateConfig = async (signer: Keypair, changes: Record<string, any>) => {
+    const config = { ...(await program.account.config.fetch(configPda)), ...changes };
+    await program.methods
+      .updateConfig(config as any)
+      .accountsPartial({
+        config: configPda,
+        admin: signer.publicKey,
+      })
+      .signers([signer])
+      .rpc();
+  };
+
+  // Create a market with a 2s challenge window; `overrides` are passed through to marketParams
+  const createMarket = async (options: string[], stake: number, overrides: Record<string, any> = {}) => {
+    const config = await program.account.config.fetch(configPda);
+    const marketId = config.nextMarketId;
+    const marketPda = getMarketPda(marketId);
+    const now = Math.floor(Date.now() / 1000);
+
+    await program.methods
+      .createMarket(
+        `Market ${marketId.toString()}`,
+        options,
+        new anchor.BN(stake),
+        new anchor.BN(now + 3600),
+        new anchor.BN(now + 7200),
+        marketParams(2, overrides)
+      )
+      .accountsPartial({
+        market: marketPda,
+        config: configPda,
+        creator: creator.publicKey,
+        feeVault: feeVault.publicKey,
+        treasury: treasury.publicKey,
+      })
+      .signers([creator])
+      .rpc();
+
+    return { marketId, marketPda };
+  };
+
+  const placeBet = async (user: Keypair, marketId: anchor.BN, optionIndex: number) => {
+    await program.methods
+      .placeBet(marketId, optionIndex)
+      .accountsPartial({
+        market: getMarketPda(marketId),
+        userBet: getUserBetPda(marketId, user.publicKey),
+        config: configPda,
+        user: user.publicKey,
+        feeVault: feeVault.publicKey,
+        treasury: treasury.publicKey,
+      })
+      .signers([user])
+      .rpc();
+  };
+
+  // Propose an outcome and finalize it once the (short) challenge window has passed
+  const settleMarket = async (proposer: Keypair, marketId: anchor.BN, outcome: number) => {
+    const userBetPda = getUserBetPda(marketId, proposer.publicKey);
+
+    await program.methods
+      .initiateSettlement(marketId, outcome)
+      .accountsPartial({
+        market: getMarketPda(marketId),
+        userBet: userBetPda,
+        proposer: proposer.publicKey,
+      })
+      .signers([proposer])
+      .rpc();
+
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+
+    await program.methods
+      .finalizeSettlement(marketId)
+      .accountsPartial({
+        market: getMarketPda(marketId),
+        creatorStats: getCreatorStatsPda(creator.publicKey),
+        outcomeStats: getOutcomeStatsPda(),
+        userBet: userBetPda,
+        caller: proposer.publicKey,
+        feeVault: feeVault.publicKey,
+        treasury: treasury.publicKey,
+      })
+      .signers([proposer])
+      .rpc();
+  };
+
+  // Settle a market as its settle authority
+  const authoritySettle = async (marketId: anchor.BN, signer: Keypair, outcome: number) => {
+    await program.methods
+      .authoritySettleMarket(marketId, outcome)
+      .accountsPartial({
+        market: getMarketPda(marketId),
+        creatorStats: getCreatorStatsPda(creator.publicKey),
+        config: configPda,
+        outcomeStats: getOutcomeStatsPda(),
+        feeVault: feeVault.publicKey,
+        treasury: treasury.publicKey,
+        settleAuthority: signer.publicKey,
+      })
+      .signers([signer])
+      .rpc();
+  };
+
   before(async () => {
     // Airdrop SOL to all accounts
     await Promise.all([
@@ -54,6 +215,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
       airdrop(user2.publicKey, 10),
       airdrop(user3.publicKey, 10),
       airdrop(feeVault.publicKey, 1), // Small amount for rent
+      airdrop(treasury.publicKey, 1),
     ]);
 
     // Find config PDA
@@ -64,16 +226,10 @@ describe("Social Bet - PRD Acceptance Tests", () => {
 
     // Initialize config
     await program.methods
-      .initializeConfig(
-        admin.publicKey,
-        feeVault.publicKey,
-        new anchor.BN(5000000), // create fee: 0.005 SOL
-        50,   // join_fee_bps: 0.5%
-        100,  // clearing_fee_bps: 1%
-        200   // settle_fee_bps: 2%
-      )
+      .initializeConfig(admin.publicKey, initialConfigParams)
       .accountsPartial({
         config: configPda,
+        outcomeStats: getOutcomeStatsPda(),
         admin: admin.publicKey,
       })
       .signers([admin])
@@ -100,13 +256,14 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           new anchor.BN(0.1 * LAMPORTS_PER_SOL), // 0.1 SOL stake
           new anchor.BN(betDeadline),
           new anchor.BN(resolveTime),
-          new anchor.BN(challengeWindow)
+          marketParams(challengeWindow)
         )
         .accountsPartial({
           market: marketPda,
           config: configPda,
           creator: creator.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([creator])
         .rpc();
@@ -118,6 +275,13 @@ describe("Social Bet - PRD Acceptance Tests", () => {
       assert.equal(market.options[1], "No");
       assert.equal(market.stakeAmount.toNumber(), 0.1 * LAMPORTS_PER_SOL);
       assert.equal(market.status, 0); // OPEN
+      assert.equal(
+        market.bump,
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("market"), marketId.toArrayLike(Buffer, "le", 8)],
+          program.programId
+        )[1]
+      );
 
       console.log("✅ Market created successfully");
     });
@@ -137,13 +301,14 @@ describe("Social Bet - PRD Acceptance Tests", () => {
             new anchor.BN(0.1 * LAMPORTS_PER_SOL),
             new anchor.BN(now + 3600),
             new anchor.BN(now + 7200),
-            new anchor.BN(300)
+            marketParams(300)
           )
           .accountsPartial({
             market: marketPda,
             config: configPda,
             creator: creator.publicKey,
             feeVault: feeVault.publicKey,
+            treasury: treasury.publicKey,
           })
           .signers([creator])
           .rpc();
@@ -153,6 +318,67 @@ describe("Social Bet - PRD Acceptance Tests", () => {
       }
       console.log("✅ Correctly rejected invalid options count");
     });
+
+    const updateStakeLimits = (min: number, max: number) =>
+      updateConfig(admin, {
+        minStakeAmount: new anchor.BN(min),
+        maxStakeAmount: new anchor.BN(max),
+      });
+
+    it("should accept a stake exactly at the configured minimum", async () => {
+      const { marketPda } = await createMarket(["Yes", "No"], 1000000);
+      const market = await program.account.market.fetch(marketPda);
+      assert.equal(market.stakeAmount.toNumber(), 1000000);
+
+      console.log("✅ Accepted stake at minimum");
+    });
+
+    it("should reject a stake below the configured minimum", async () => {
+      try {
+        await createMarket(["Yes", "No"], 999999);
+        assert.fail("Should have rejected stake below minimum");
+      } catch (e: any) {
+        assert.include(e.message, "StakeTooSmall");
+      }
+
+      console.log("✅ Correctly rejected stake below minimum");
+    });
+
+    it("should reject a stake above the configured maximum", async () => {
+      await updateStakeLimits(1000000, LAMPORTS_PER_SOL);
+
+      try {
+        await createMarket(["Yes", "No"], LAMPORTS_PER_SOL + 1);
+        assert.fail("Should have rejected stake above maximum");
+      } catch (e: any) {
+        assert.include(e.message, "StakeTooLarge");
+      } finally {
+        await updateStakeLimits(1000000, 0);
+      }
+
+      console.log("✅ Correctly rejected stake above maximum");
+    });
+
+    it("should treat a zero minimum as one lamport", async () => {
+      await updateStakeLimits(0, 0);
+
+      try {
+        const { marketPda } = await createMarket(["Yes", "No"], 1);
+        const market = await program.account.market.fetch(marketPda);
+        assert.equal(market.stakeAmount.toNumber(), 1);
+
+        try {
+          await createMarket(["Yes", "No"], 0);
+          assert.fail("Should have rejected zero stake");
+        } catch (e: any) {
+          assert.include(e.message, "StakeTooSmall");
+        }
+      } finally {
+        await updateStakeLimits(1000000, 0);
+      }
+
+      console.log("✅ Zero minimum still requires one lamport");
+    });
   });
 
   describe("2. Betting", () => {
@@ -174,13 +400,14 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           new anchor.BN(0.1 * LAMPORTS_PER_SOL),
           new anchor.BN(now + 3600),
           new anchor.BN(now + 7200),
-          new anchor.BN(300)
+          marketParams(300)
         )
         .accountsPartial({
           market: marketPda,
           config: configPda,
           creator: creator.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([creator])
         .rpc();
@@ -197,6 +424,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           config: configPda,
           user: user1.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user1])
         .rpc();
@@ -224,6 +452,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           config: configPda,
           user: user1.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user1])
         .rpc();
@@ -247,6 +476,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
             config: configPda,
             user: user1.publicKey,
             feeVault: feeVault.publicKey,
+            treasury: treasury.publicKey,
           })
           .signers([user1])
           .rpc();
@@ -271,6 +501,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           config: configPda,
           user: user2.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user2])
         .rpc();
@@ -284,6 +515,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           config: configPda,
           user: user3.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user3])
         .rpc();
@@ -316,13 +548,14 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           new anchor.BN(0.1 * LAMPORTS_PER_SOL),
           new anchor.BN(now + 2), // 2 seconds deadline
           new anchor.BN(now + 5),
-          new anchor.BN(1)
+          marketParams(1)
         )
         .accountsPartial({
           market: marketPda,
           config: configPda,
           creator: creator.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([creator])
         .rpc();
@@ -341,6 +574,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
             config: configPda,
             user: user1.publicKey,
             feeVault: feeVault.publicKey,
+            treasury: treasury.publicKey,
           })
           .signers([user1])
           .rpc();
@@ -372,13 +606,14 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           new anchor.BN(0.5 * LAMPORTS_PER_SOL),
           new anchor.BN(now + 3600), // 1 hour bet deadline (long enough for early resolution test)
           new anchor.BN(now + 7200), // 2 hours resolve time
-          new anchor.BN(2) // 2 second challenge window
+          marketParams(2) // 2 second challenge window
         )
         .accountsPartial({
           market: marketPda,
           config: configPda,
           creator: creator.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([creator])
         .rpc();
@@ -395,6 +630,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           config: configPda,
           user: user1.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user1])
         .rpc();
@@ -407,6 +643,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           config: configPda,
           user: user2.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user2])
         .rpc();
@@ -448,6 +685,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
             config: configPda,
             user: user3.publicKey,
             feeVault: feeVault.publicKey,
+            treasury: treasury.publicKey,
           })
           .signers([user3])
           .rpc();
@@ -480,7 +718,11 @@ describe("Social Bet - PRD Acceptance Tests", () => {
         .resolveDispute(marketId, 0) // Admin decides "Win"
         .accountsPartial({
           market: marketPda,
+          creatorStats: getCreatorStatsPda(creator.publicKey),
+          outcomeStats: getOutcomeStatsPda(),
           config: configPda,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
           admin: admin.publicKey,
         })
         .signers([admin])
@@ -512,13 +754,14 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           new anchor.BN(0.2 * LAMPORTS_PER_SOL),
           new anchor.BN(now + 3600), // Long deadline - we'll use early resolution
           new anchor.BN(now + 7200),
-          new anchor.BN(2) // 2 second challenge window
+          marketParams(2) // 2 second challenge window
         )
         .accountsPartial({
           market: marketPda,
           config: configPda,
           creator: creator.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([creator])
         .rpc();
@@ -534,6 +777,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           config: configPda,
           user: user1.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user1])
         .rpc();
@@ -546,6 +790,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           config: configPda,
           user: user2.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user2])
         .rpc();
@@ -573,8 +818,12 @@ describe("Social Bet - PRD Acceptance Tests", () => {
         .finalizeSettlement(marketId)
         .accountsPartial({
           market: marketPda,
+          creatorStats: getCreatorStatsPda(creator.publicKey),
+          outcomeStats: getOutcomeStatsPda(),
           userBet: user1BetPda,
           caller: user1.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user1])
         .rpc();
@@ -607,13 +856,14 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           new anchor.BN(1 * LAMPORTS_PER_SOL),
           new anchor.BN(now + 3600), // Long deadline - using early resolution
           new anchor.BN(now + 7200),
-          new anchor.BN(2)
+          marketParams(2)
         )
         .accountsPartial({
           market: marketPda,
           config: configPda,
           creator: creator.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([creator])
         .rpc();
@@ -629,6 +879,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           config: configPda,
           user: user1.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user1])
         .rpc();
@@ -641,6 +892,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           config: configPda,
           user: user2.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user2])
         .rpc();
@@ -666,8 +918,12 @@ describe("Social Bet - PRD Acceptance Tests", () => {
         .finalizeSettlement(marketId)
         .accountsPartial({
           market: marketPda,
+          creatorStats: getCreatorStatsPda(creator.publicKey),
+          outcomeStats: getOutcomeStatsPda(),
           userBet: user1BetPda,
           caller: user1.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user1])
         .rpc();
@@ -684,6 +940,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           config: configPda,
           user: user1.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user1])
         .rpc();
@@ -692,8 +949,8 @@ describe("Social Bet - PRD Acceptance Tests", () => {
       const userBet = await program.account.userBet.fetch(user1BetPda);
 
       assert.isTrue(userBet.claimed);
-      // Winner should have received the total pool (2 SOL) minus 1% fee
-      // Expected: ~1.98 SOL gain
+      // Winner should have received the total pool (2 SOL) minus 2% settle fee and 1% clearing fee
+      // Expected: ~1.94 SOL back
       const gain = user1BalanceAfter - user1BalanceBefore;
       console.log(`Winner gain: ${gain / LAMPORTS_PER_SOL} SOL`);
       assert.isTrue(gain > 0.9 * LAMPORTS_PER_SOL); // Should gain at least ~0.9 SOL (original bet back + winnings - fees)
@@ -713,6 +970,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
             config: configPda,
             user: user2.publicKey,
             feeVault: feeVault.publicKey,
+            treasury: treasury.publicKey,
           })
           .signers([user2])
           .rpc();
@@ -736,6 +994,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
             config: configPda,
             user: user1.publicKey,
             feeVault: feeVault.publicKey,
+            treasury: treasury.publicKey,
           })
           .signers([user1])
           .rpc();
@@ -766,13 +1025,14 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           new anchor.BN(0.5 * LAMPORTS_PER_SOL),
           new anchor.BN(now + 3600),
           new anchor.BN(now + 7200),
-          new anchor.BN(300)
+          marketParams(300)
         )
         .accountsPartial({
           market: marketPda,
           config: configPda,
           creator: creator.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([creator])
         .rpc();
@@ -787,6 +1047,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           config: configPda,
           user: user1.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([user1])
         .rpc();
@@ -797,6 +1058,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
         .adminCancelMarket(marketId)
         .accountsPartial({
           market: marketPda,
+          creatorStats: getCreatorStatsPda(creator.publicKey),
           config: configPda,
           admin: admin.publicKey,
         })
@@ -818,6 +1080,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
         .accountsPartial({
           market: marketPda,
           userBet: user1BetPda,
+          config: configPda,
           user: user1.publicKey,
         })
         .signers([user1])
@@ -847,13 +1110,14 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           new anchor.BN(0.1 * LAMPORTS_PER_SOL),
           new anchor.BN(now + 3600),
           new anchor.BN(now + 7200),
-          new anchor.BN(300)
+          marketParams(300)
         )
         .accountsPartial({
           market: newMarketPda,
           config: configPda,
           creator: creator.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([creator])
         .rpc();
@@ -863,6 +1127,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           .adminCancelMarket(newMarketId)
           .accountsPartial({
             market: newMarketPda,
+            creatorStats: getCreatorStatsPda(creator.publicKey),
             config: configPda,
             admin: user1.publicKey, // Not admin
           })
@@ -878,8 +1143,6 @@ describe("Social Bet - PRD Acceptance Tests", () => {
   });
 
   describe("8. Admin Management", () => {
-    const newAdmin = Keypair.generate();
-
     before(async () => {
       await airdrop(newAdmin.publicKey, 5);
     });
@@ -939,6 +1202,40 @@ describe("Social Bet - PRD Acceptance Tests", () => {
       console.log("✅ Correctly rejected zero address admin");
     });
 
+    it("should allow admin to rotate only the fee vault", async () => {
+      const rotatedVault = Keypair.generate();
+      const before = await program.account.config.fetch(configPda);
+
+      await program.methods
+        .setFeeVault(rotatedVault.publicKey)
+        .accountsPartial({
+          config: configPda,
+          admin: newAdmin.publicKey,
+        })
+        .signers([newAdmin])
+        .rpc();
+
+      let config = await program.account.config.fetch(configPda);
+      assert.equal(config.feeVault.toBase58(), rotatedVault.publicKey.toBase58());
+      assert.equal(config.createFee.toNumber(), before.createFee.toNumber());
+      assert.equal(config.joinFeeBps, before.joinFeeBps);
+
+      // Restore the original vault for the remaining tests
+      await program.methods
+        .setFeeVault(feeVault.publicKey)
+        .accountsPartial({
+          config: configPda,
+          admin: newAdmin.publicKey,
+        })
+        .signers([newAdmin])
+        .rpc();
+
+      config = await program.account.config.fetch(configPda);
+      assert.equal(config.feeVault.toBase58(), feeVault.publicKey.toBase58());
+
+      console.log("✅ Fee vault rotated without touching fees");
+    });
+
     it("new admin can perform admin actions", async () => {
       // Create a market to cancel
       const config = await program.account.config.fetch(configPda);
@@ -954,13 +1251,14 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           new anchor.BN(0.1 * LAMPORTS_PER_SOL),
           new anchor.BN(now + 3600),
           new anchor.BN(now + 7200),
-          new anchor.BN(300)
+          marketParams(300)
         )
         .accountsPartial({
           market: marketPda,
           config: configPda,
           creator: creator.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([creator])
         .rpc();
@@ -970,6 +1268,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
         .adminCancelMarket(marketId)
         .accountsPartial({
           market: marketPda,
+          creatorStats: getCreatorStatsPda(creator.publicKey),
           config: configPda,
           admin: newAdmin.publicKey,
         })
@@ -997,13 +1296,14 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           new anchor.BN(0.1 * LAMPORTS_PER_SOL),
           new anchor.BN(now + 3600),
           new anchor.BN(now + 7200),
-          new anchor.BN(300)
+          marketParams(300)
         )
         .accountsPartial({
           market: marketPda,
           config: configPda,
           creator: creator.publicKey,
           feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
         })
         .signers([creator])
         .rpc();
@@ -1014,6 +1314,7 @@ describe("Social Bet - PRD Acceptance Tests", () => {
           .adminCancelMarket(marketId)
           .accountsPartial({
             market: marketPda,
+            creatorStats: getCreatorStatsPda(creator.publicKey),
             config: configPda,
             admin: admin.publicKey, // Old admin
           })
@@ -1027,4 +1328,2214 @@ describe("Social Bet - PRD Acceptance Tests", () => {
       console.log("✅ Old admin correctly rejected");
     });
   });
+
+  describe("9. Empty Winning Option", () => {
+    let marketId: anchor.BN;
+
+    before(async () => {
+      ({ marketId } = await createMarket(["Nobody", "Everybody"], 0.1 * LAMPORTS_PER_SOL));
+      await placeBet(user1, marketId, 1);
+      await placeBet(user2, marketId, 1);
+      await settleMarket(user1, marketId, 0);
+    });
+
+    it("should refund bettors when nobody backed the winning option", async () => {
+      const user1BetPda = getUserBetPda(marketId, user1.publicKey);
+      const marketPda = getMarketPda(marketId);
+      const marketBalanceBefore = await provider.connection.getBalance(marketPda);
+
+      await program.methods
+        .claimPrize(marketId)
+        .accountsPartial({
+          market: marketPda,
+          userBet: user1BetPda,
+          config: configPda,
+          user: user1.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+        })
+        .signers([user1])
+        .rpc();
+
+      const marketBalanceAfter = await provider.connection.getBalance(marketPda);
+      const userBet = await program.account.userBet.fetch(user1BetPda);
+
+      assert.isTrue(userBet.claimed);
+      // Full stake back, no clearing fee
+      assert.equal(marketBalanceBefore - marketBalanceAfter, 0.1 * LAMPORTS_PER_SOL);
+
+      console.log("✅ Stake refunded when winning option was empty");
+    });
+  });
+
+  describe("10. Unclaimed Winnings Forfeit", () => {
+    let marketId: anchor.BN;
+    let marketPda: PublicKey;
+
+    before(async () => {
+      await updateConfig(newAdmin, { claimWindow: new anchor.BN(2) });
+
+      ({ marketId, marketPda } = await createMarket(["Win", "Lose"], 0.1 * LAMPORTS_PER_SOL));
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+      await settleMarket(user1, marketId, 0);
+    });
+
+    after(async () => {
+      await updateConfig(newAdmin, { claimWindow: new anchor.BN(0) });
+    });
+
+    it("should set a claim deadline on settlement", async () => {
+      const market = await program.account.market.fetch(marketPda);
+      assert.isNotNull(market.claimDeadline);
+
+      console.log("✅ Claim deadline recorded");
+    });
+
+    it("should sweep unclaimed winnings after the deadline", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+
+      const vaultBefore = await provider.connection.getBalance(feeVault.publicKey);
+      const marketBefore = await provider.connection.getBalance(marketPda);
+
+      await program.methods
+        .forfeitUnclaimed(marketId)
+        .accountsPartial({
+          market: marketPda,
+          config: configPda,
+          feeVault: feeVault.publicKey,
+          caller: user3.publicKey,
+        })
+        .signers([user3])
+        .rpc();
+
+      const vaultAfter = await provider.connection.getBalance(feeVault.publicKey);
+      const marketAfter = await provider.connection.getBalance(marketPda);
+      const market = await program.account.market.fetch(marketPda);
+
+      assert.equal(market.status, 6); // FORFEITED
+      // Whole pool minus the settle fee already taken, rent left behind
+      const swept = marketBefore - marketAfter;
+      assert.equal(vaultAfter - vaultBefore, swept);
+      assert.equal(swept, 0.2 * LAMPORTS_PER_SOL - market.settleFee.toNumber());
+
+      console.log("✅ Unclaimed winnings swept to fee vault");
+    });
+
+    it("should reject forfeit before the deadline", async () => {
+      const { marketId: freshId, marketPda: freshPda } = await createMarket(
+        ["Win", "Lose"],
+        0.1 * LAMPORTS_PER_SOL
+      );
+      await placeBet(user1, freshId, 0);
+      await updateConfig(newAdmin, { claimWindow: new anchor.BN(3600) });
+      await settleMarket(user1, freshId, 0);
+
+      try {
+        await program.methods
+          .forfeitUnclaimed(freshId)
+          .accountsPartial({
+            market: freshPda,
+            config: configPda,
+            feeVault: feeVault.publicKey,
+            caller: user3.publicKey,
+          })
+          .signers([user3])
+          .rpc();
+        assert.fail("Should have rejected forfeit before deadline");
+      } catch (e: any) {
+        assert.include(e.message, "ClaimWindowNotEnded");
+      }
+
+      console.log("✅ Correctly rejected early forfeit");
+    });
+  });
+
+  describe("11. Participant Limit", () => {
+    after(async () => {
+      await updateConfig(newAdmin, { maxParticipants: 0 });
+    });
+
+    it("should reject new bettors once the market is full", async () => {
+      await updateConfig(newAdmin, { maxParticipants: 2 });
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+
+      try {
+        await placeBet(user3, marketId, 0);
+        assert.fail("Should have rejected third participant");
+      } catch (e: any) {
+        assert.include(e.message, "MarketFull");
+      }
+
+      // Existing bettors can still add to their position
+      await placeBet(user1, marketId, 0);
+      const market = await program.account.market.fetch(marketPda);
+      assert.equal(market.optionParticipants[0], 1);
+      assert.equal(market.optionTotals[0].toNumber(), 0.2 * LAMPORTS_PER_SOL);
+
+      console.log("✅ Participant limit enforced");
+    });
+  });
+
+  describe("12. Market Summary", () => {
+    it("should return market state as return data", async () => {
+      const { marketId } = await createMarket(["A", "B", "C"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 2);
+
+      const summary = await program.methods
+        .getMarketSummary(marketId)
+        .accountsPartial({ market: getMarketPda(marketId) })
+        .view();
+
+      assert.equal(summary.id.toString(), marketId.toString());
+      assert.equal(summary.status, 0); // OPEN
+      assert.equal(summary.optionsCount, 3);
+      assert.equal(summary.optionTotals[2].toNumber(), 0.1 * LAMPORTS_PER_SOL);
+      assert.equal(summary.totalPool.toNumber(), 0.1 * LAMPORTS_PER_SOL);
+      assert.isNull(summary.finalOutcome);
+
+      console.log("✅ Market summary decoded from return data");
+    });
+  });
+
+  describe("13. Market Metadata", () => {
+    let marketId: anchor.BN;
+    let marketPda: PublicKey;
+
+    const getMetadataPda = (market: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("meta"), market.toBuffer()],
+        program.programId
+      )[0];
+
+    before(async () => {
+      ({ marketId, marketPda } = await createMarket(["Yes", "No"], 0.1 * LAMPORTS_PER_SOL));
+    });
+
+    it("should let the creator attach metadata", async () => {
+      await program.methods
+        .addMarketMetadata(marketId, "Will $BONK hit $0.00005 by Friday?", "https://example.com/bonk.png")
+        .accountsPartial({
+          market: marketPda,
+          metadata: getMetadataPda(marketPda),
+          creator: creator.publicKey,
+        })
+        .signers([creator])
+        .rpc();
+
+      const metadata = await program.account.marketMetadata.fetch(getMetadataPda(marketPda));
+      assert.equal(metadata.market.toBase58(), marketPda.toBase58());
+      assert.equal(metadata.description, "Will $BONK hit $0.00005 by Friday?");
+
+      console.log("✅ Market metadata attached");
+    });
+
+    it("should reject metadata from a non-creator", async () => {
+      const { marketId: otherId, marketPda: otherPda } = await createMarket(
+        ["Yes", "No"],
+        0.1 * LAMPORTS_PER_SOL
+      );
+
+      try {
+        await program.methods
+          .addMarketMetadata(otherId, "Hijacked", "")
+          .accountsPartial({
+            market: otherPda,
+            metadata: getMetadataPda(otherPda),
+            creator: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        assert.fail("Should have rejected non-creator");
+      } catch (e: any) {
+        assert.include(e.message, "NotCreator");
+      }
+
+      console.log("✅ Correctly rejected non-creator metadata");
+    });
+  });
+
+  describe("14. Join Cutoff", () => {
+    after(async () => {
+      await updateConfig(newAdmin, { joinCutoff: new anchor.BN(0) });
+    });
+
+    it("should accept bets before the cutoff and reject them at it", async () => {
+      // createMarket sets bet_deadline one hour out
+      const { marketId } = await createMarket(["Up", "Down"], 0.1 * LAMPORTS_PER_SOL);
+
+      await updateConfig(newAdmin, { joinCutoff: new anchor.BN(3590) });
+      await placeBet(user1, marketId, 0);
+
+      await updateConfig(newAdmin, { joinCutoff: new anchor.BN(3600) });
+      try {
+        await placeBet(user2, marketId, 1);
+        assert.fail("Should have rejected bet inside the cutoff");
+      } catch (e: any) {
+        assert.include(e.message, "JoinWindowClosed");
+      }
+
+      console.log("✅ Join cutoff enforced");
+    });
+  });
+
+  describe("15. Settle Fee Floor", () => {
+    after(async () => {
+      await updateConfig(newAdmin, { minSettleFee: new anchor.BN(0) });
+    });
+
+    it("should charge the floor when the bps fee rounds below it", async () => {
+      await updateConfig(newAdmin, { minSettleFee: new anchor.BN(100000) });
+
+      // Two bets just above the minimum stake: 2% of 2_000_002 is well under the floor
+      const { marketId, marketPda } = await createMarket(["Yes", "No"], 1000001);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+
+      const vaultBefore = await provider.connection.getBalance(feeVault.publicKey);
+      await settleMarket(user1, marketId, 0);
+      const vaultAfter = await provider.connection.getBalance(feeVault.publicKey);
+
+      const market = await program.account.market.fetch(marketPda);
+      assert.equal(market.settleFee.toNumber(), 100000);
+      assert.equal(vaultAfter - vaultBefore, 100000);
+
+      // Winner's share is drawn from the pool net of the settle fee
+      const user1BetPda = getUserBetPda(marketId, user1.publicKey);
+      const marketBalanceBefore = await provider.connection.getBalance(marketPda);
+      await program.methods
+        .claimPrize(marketId)
+        .accountsPartial({
+          market: marketPda,
+          userBet: user1BetPda,
+          config: configPda,
+          user: user1.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+        })
+        .signers([user1])
+        .rpc();
+      const marketBalanceAfter = await provider.connection.getBalance(marketPda);
+      assert.equal(marketBalanceBefore - marketBalanceAfter, 2000002 - 100000);
+
+      console.log("✅ Settle fee floor applied");
+    });
+  });
+
+  describe("16. Per-Option Join Fees", () => {
+    it("should charge each option its own join fee", async () => {
+      // Option 0 uses the config default (0.5%), option 1 overrides to 5%
+      const stake = 0.1 * LAMPORTS_PER_SOL;
+      const { marketId } = await createMarket(["Underdog", "Favourite"], stake, { optionFeeBps: [0, 500] });
+
+      let vaultBefore = await provider.connection.getBalance(feeVault.publicKey);
+      await placeBet(user1, marketId, 0);
+      let vaultAfter = await provider.connection.getBalance(feeVault.publicKey);
+      assert.equal(vaultAfter - vaultBefore, (stake * 50) / 10000);
+
+      vaultBefore = vaultAfter;
+      await placeBet(user2, marketId, 1);
+      vaultAfter = await provider.connection.getBalance(feeVault.publicKey);
+      assert.equal(vaultAfter - vaultBefore, (stake * 500) / 10000);
+
+      console.log("✅ Per-option join fees applied");
+    });
+
+    it("should reject a fee override above 100%", async () => {
+      try {
+        await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL, { optionFeeBps: [0, 10001] });
+        assert.fail("Should have rejected fee above 10000 bps");
+      } catch (e: any) {
+        assert.include(e.message, "InvalidFeeBps");
+      }
+
+      console.log("✅ Correctly rejected invalid fee override");
+    });
+  });
+
+  describe("17. Admin Fallback Settlement", () => {
+    it("should reject admin settlement before the grace period", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 0);
+
+      try {
+        await program.methods
+          .adminSettleMarket(marketId, 0)
+          .accountsPartial({
+            market: marketPda,
+            creatorStats: getCreatorStatsPda(creator.publicKey),
+            outcomeStats: getOutcomeStatsPda(),
+            config: configPda,
+            feeVault: feeVault.publicKey,
+            treasury: treasury.publicKey,
+            admin: newAdmin.publicKey,
+          })
+          .signers([newAdmin])
+          .rpc();
+        assert.fail("Should have rejected admin settlement inside grace period");
+      } catch (e: any) {
+        assert.include(e.message, "AdminSettleTooEarly");
+      }
+
+      console.log("✅ Correctly rejected early admin settlement");
+    });
+  });
+
+  describe("18. Creator Fee Overrides", () => {
+    after(async () => {
+      await updateConfig(newAdmin, { minFeeBps: 0, maxFeeBps: 10000 });
+    });
+
+    it("should reject overrides outside the admin bounds", async () => {
+      await updateConfig(newAdmin, { minFeeBps: 10, maxFeeBps: 300 });
+
+      try {
+        await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL, { clearingFeeBps: 301 });
+        assert.fail("Should have rejected clearing fee above max");
+      } catch (e: any) {
+        assert.include(e.message, "FeeOutOfBounds");
+      }
+
+      try {
+        await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL, { optionFeeBps: [5, 0] });
+        assert.fail("Should have rejected join fee below min");
+      } catch (e: any) {
+        assert.include(e.message, "FeeOutOfBounds");
+      }
+
+      console.log("✅ Out-of-bounds fee overrides rejected");
+    });
+
+    it("should apply an in-bounds clearing fee override on claim", async () => {
+      const stake = 0.1 * LAMPORTS_PER_SOL;
+      const { marketId, marketPda } = await createMarket(["A", "B"], stake, { clearingFeeBps: 300 });
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+      await settleMarket(user1, marketId, 0);
+
+      const market = await program.account.market.fetch(marketPda);
+      const share = 2 * stake - market.settleFee.toNumber();
+      const vaultBefore = await provider.connection.getBalance(feeVault.publicKey);
+
+      await program.methods
+        .claimPrize(marketId)
+        .accountsPartial({
+          market: marketPda,
+          userBet: getUserBetPda(marketId, user1.publicKey),
+          config: configPda,
+          user: user1.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+        })
+        .signers([user1])
+        .rpc();
+
+      const vaultAfter = await provider.connection.getBalance(feeVault.publicKey);
+      assert.equal(vaultAfter - vaultBefore, Math.floor((share * 300) / 10000));
+
+      console.log("✅ Market clearing fee override applied");
+    });
+  });
+
+  describe("19. Contested Settlement", () => {
+    before(async () => {
+      await updateConfig(newAdmin, { requireContested: true });
+    });
+
+    after(async () => {
+      await updateConfig(newAdmin, { requireContested: false });
+    });
+
+    it("should refund a one-sided market instead of settling it", async () => {
+      const stake = 0.1 * LAMPORTS_PER_SOL;
+      const { marketId, marketPda } = await createMarket(["A", "B"], stake);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 0);
+
+      const vaultBefore = await provider.connection.getBalance(feeVault.publicKey);
+      await settleMarket(user1, marketId, 0);
+      const vaultAfter = await provider.connection.getBalance(feeVault.publicKey);
+
+      const market = await program.account.market.fetch(marketPda);
+      assert.equal(market.status, 5); // CANCELLED
+      assert.equal(vaultAfter, vaultBefore); // no settle fee
+
+      const marketBalanceBefore = await provider.connection.getBalance(marketPda);
+      await program.methods
+        .claimCancelledRefund(marketId)
+        .accountsPartial({
+          market: marketPda,
+          userBet: getUserBetPda(marketId, user2.publicKey),
+          config: configPda,
+          user: user2.publicKey,
+        })
+        .signers([user2])
+        .rpc();
+      const marketBalanceAfter = await provider.connection.getBalance(marketPda);
+      assert.equal(marketBalanceBefore - marketBalanceAfter, stake);
+
+      console.log("✅ One-sided market refunded");
+    });
+
+    it("should settle a two-sided market normally", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+      await settleMarket(user1, marketId, 0);
+
+      const market = await program.account.market.fetch(marketPda);
+      assert.equal(market.status, 4); // SETTLED
+      assert.equal(market.finalOutcome, 0);
+
+      console.log("✅ Two-sided market settled");
+    });
+  });
+
+  describe("20. Create Fee Refund On Cancel", () => {
+    before(async () => {
+      await updateConfig(newAdmin, { refundCreateFeeOnCancel: true });
+    });
+
+    after(async () => {
+      await updateConfig(newAdmin, { refundCreateFeeOnCancel: false });
+    });
+
+    it("should make the creator whole on an admin cancellation", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      const config = await program.account.config.fetch(configPda);
+      const creatorBefore = await provider.connection.getBalance(creator.publicKey);
+
+      await program.methods
+        .adminCancelMarket(marketId)
+        .accountsPartial({
+          market: marketPda,
+          creatorStats: getCreatorStatsPda(creator.publicKey),
+          config: configPda,
+          admin: newAdmin.publicKey,
+          feeVault: feeVault.publicKey,
+          creator: creator.publicKey,
+        })
+        .signers([newAdmin, feeVault])
+        .rpc();
+
+      const creatorAfter = await provider.connection.getBalance(creator.publicKey);
+      assert.equal(creatorAfter - creatorBefore, config.createFee.toNumber());
+
+      console.log("✅ Create fee refunded to creator");
+    });
+  });
+
+  describe("21. Payout Rounding", () => {
+    it("should pay out exactly the prize pool across many winners", async () => {
+      // Odd stake so proportional shares don't divide evenly
+      const stake = 1000003;
+      const { marketId, marketPda } = await createMarket(["A", "B"], stake);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user3, marketId, 0);
+      await placeBet(user3, marketId, 0);
+      await placeBet(creator, marketId, 0);
+      await placeBet(user2, marketId, 1);
+      await settleMarket(user1, marketId, 0);
+
+      // Built with `--features debug-asserts`, every claim also fails if it leaves the market insolvent
+      const winners = [user1, user3, creator];
+      for (const winner of winners) {
+        await program.methods
+          .claimPrize(marketId)
+          .accountsPartial({
+            market: marketPda,
+            userBet: getUserBetPda(marketId, winner.publicKey),
+            config: configPda,
+            user: winner.publicKey,
+            feeVault: feeVault.publicKey,
+            treasury: treasury.publicKey,
+          })
+          .signers([winner])
+          .rpc();
+      }
+
+      const market = await program.account.market.fetch(marketPda);
+      const prizePool = market.totalPool.toNumber() - market.settleFee.toNumber();
+
+      // The last winner absorbs the rounding dust, so the prize pool is paid out exactly
+      assert.equal(market.totalClaimed.toNumber(), prizePool);
+      assert.equal(market.distributable.toNumber(), 0);
+      assert.equal(market.winnersRemaining, 0);
+
+      // Only the rent reserve is left behind
+      const marketInfo = await provider.connection.getAccountInfo(marketPda);
+      const rentReserve = await provider.connection.getMinimumBalanceForRentExemption(marketInfo!.data.length);
+      assert.equal(marketInfo!.lamports, rentReserve);
+
+      console.log("✅ Prize pool paid out exactly, no dust left");
+    });
+  });
+
+  describe("22. Config Initialization Guard", () => {
+    it("should reject a second initialization", async () => {
+      try {
+        await program.methods
+          .initializeConfig(user1.publicKey, initialConfigParams)
+          .accountsPartial({
+            config: configPda,
+            outcomeStats: getOutcomeStatsPda(),
+            admin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        assert.fail("Should have rejected re-initialization");
+      } catch (e: any) {
+        assert.include((e.logs ?? []).join("\n") + e.message, "already in use");
+      }
+
+      const config = await program.account.config.fetch(configPda);
+      assert.equal(config.admin.toBase58(), newAdmin.publicKey.toBase58());
+
+      console.log("✅ Correctly rejected re-initialization");
+    });
+  });
+
+  describe("23. Claim And Close", () => {
+    it("should pay the winner and close their UserBet", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+      await settleMarket(user1, marketId, 0);
+
+      const user1BetPda = getUserBetPda(marketId, user1.publicKey);
+      const balanceBefore = await provider.connection.getBalance(user1.publicKey);
+
+      await program.methods
+        .claimAndClose(marketId)
+        .accountsPartial({
+          market: marketPda,
+          userBet: user1BetPda,
+          config: configPda,
+          user: user1.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+        })
+        .signers([user1])
+        .rpc();
+
+      const balanceAfter = await provider.connection.getBalance(user1.publicKey);
+      assert.isTrue(balanceAfter - balanceBefore > 0.15 * LAMPORTS_PER_SOL);
+      assert.isNull(await provider.connection.getAccountInfo(user1BetPda));
+
+      console.log("✅ Prize claimed and UserBet closed");
+    });
+  });
+
+  describe("24. Settlement Lag", () => {
+    after(async () => {
+      await updateConfig(newAdmin, { maxSettleLag: new anchor.BN(7 * 24 * 3600) });
+    });
+
+    it("should only allow refunds once the settlement lag has passed", async () => {
+      await updateConfig(newAdmin, { maxSettleLag: new anchor.BN(1) });
+
+      const config = await program.account.config.fetch(configPda);
+      const marketId = config.nextMarketId;
+      const marketPda = getMarketPda(marketId);
+      const now = Math.floor(Date.now() / 1000);
+
+      await program.methods
+        .createMarket(
+          "Lagging market",
+          ["Yes", "No"],
+          new anchor.BN(0.1 * LAMPORTS_PER_SOL),
+          new anchor.BN(now + 4),
+          new anchor.BN(now + 5),
+          marketParams(1)
+        )
+        .accountsPartial({
+          market: marketPda,
+          config: configPda,
+          creator: creator.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+        })
+        .signers([creator])
+        .rpc();
+      await placeBet(user1, marketId, 0);
+
+      // resolve_time + lag is now behind us
+      await new Promise((resolve) => setTimeout(resolve, 8000));
+
+      const user1BetPda = getUserBetPda(marketId, user1.publicKey);
+      try {
+        await program.methods
+          .initiateSettlement(marketId, 0)
+          .accountsPartial({
+            market: marketPda,
+            userBet: user1BetPda,
+            proposer: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        assert.fail("Should have rejected settlement outside the lag window");
+      } catch (e: any) {
+        assert.include(e.message, "RefundWindowOpen");
+      }
+
+      await program.methods
+        .refundBet(marketId)
+        .accountsPartial({
+          market: marketPda,
+          creatorStats: getCreatorStatsPda(creator.publicKey),
+          userBet: user1BetPda,
+          user: user1.publicKey,
+        })
+        .signers([user1])
+        .rpc();
+
+      const userBet = await program.account.userBet.fetch(user1BetPda);
+      assert.isTrue(userBet.claimed);
+
+      console.log("✅ Late settlement rejected, refund allowed");
+    });
+
+    it("should settle just inside the window under the lag fixed at creation", async () => {
+      await updateConfig(newAdmin, { maxSettleLag: new anchor.BN(20) });
+
+      const config = await program.account.config.fetch(configPda);
+      const marketId = config.nextMarketId;
+      const marketPda = getMarketPda(marketId);
+      const now = Math.floor(Date.now() / 1000);
+
+      await program.methods
+        .createMarket(
+          "Market inside the lag",
+          ["Yes", "No"],
+          new anchor.BN(0.1 * LAMPORTS_PER_SOL),
+          new anchor.BN(now + 4),
+          new anchor.BN(now + 5),
+          marketParams(1)
+        )
+        .accountsPartial({
+          market: marketPda,
+          config: configPda,
+          creator: creator.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+        })
+        .signers([creator])
+        .rpc();
+      await placeBet(user1, marketId, 0);
+
+      let market = await program.account.market.fetch(marketPda);
+      assert.equal(market.refundDeadline.toNumber(), market.resolveTime.toNumber() + 20);
+
+      // Shrinking the lag afterwards must not make this market refund-only
+      await updateConfig(newAdmin, { maxSettleLag: new anchor.BN(1) });
+
+      // Past resolve_time + the new lag, still inside the stored deadline
+      await new Promise((resolve) => setTimeout(resolve, 8000));
+
+      await program.methods
+        .initiateSettlement(marketId, 0)
+        .accountsPartial({
+          market: marketPda,
+          userBet: getUserBetPda(marketId, user1.publicKey),
+          proposer: user1.publicKey,
+        })
+        .signers([user1])
+        .rpc();
+
+      market = await program.account.market.fetch(marketPda);
+      assert.equal(market.proposedOutcome, 0);
+
+      console.log("✅ Settlement accepted inside the stored refund deadline");
+    });
+  });
+
+  describe("25. Claim Scan", () => {
+    it("should report claimable and lost bets in one call", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+      await settleMarket(user1, marketId, 0);
+
+      const results: any[] = [];
+      const listener = program.addEventListener("claimScanResult", (event) => {
+        results.push(event);
+      });
+
+      await program.methods
+        .scanClaims()
+        .accountsPartial({ config: configPda })
+        .remainingAccounts([
+          { pubkey: marketPda, isSigner: false, isWritable: false },
+          { pubkey: getUserBetPda(marketId, user1.publicKey), isSigner: false, isWritable: false },
+          { pubkey: marketPda, isSigner: false, isWritable: false },
+          { pubkey: getUserBetPda(marketId, user2.publicKey), isSigner: false, isWritable: false },
+        ])
+        .rpc();
+
+      await new Promise((resolve) => setTimeout(resolve, 1000));
+      await program.removeEventListener(listener);
+
+      assert.equal(results.length, 2);
+      assert.equal(results[0].status, 0); // Claimable
+      assert.isTrue(results[0].amount.toNumber() > 0);
+      assert.equal(results[1].status, 3); // Lost
+      assert.equal(results[1].amount.toNumber(), 0);
+
+      console.log("✅ Claim scan reported both bets");
+    });
+  });
+
+  describe("26. Payout Cap", () => {
+    it("should cap a lopsided win and refund the overflow to the losing side", async () => {
+      const stake = 0.1 * LAMPORTS_PER_SOL;
+      const { marketId, marketPda } = await createMarket(["Long shot", "Favourite"], stake, { maxPayoutMultiple: 2 });
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+      await placeBet(user2, marketId, 1);
+      await placeBet(user2, marketId, 1);
+      await settleMarket(user1, marketId, 0);
+
+      const claim = (user: Keypair) =>
+        program.methods
+          .claimPrize(marketId)
+          .accountsPartial({
+            market: marketPda,
+            userBet: getUserBetPda(marketId, user.publicKey),
+            config: configPda,
+            user: user.publicKey,
+            feeVault: feeVault.publicKey,
+            treasury: treasury.publicKey,
+          })
+          .signers([user])
+          .rpc();
+
+      // The single winner is capped at 2x their stake
+      await claim(user1);
+      let market = await program.account.market.fetch(marketPda);
+      assert.equal(market.totalClaimed.toNumber(), 2 * stake);
+
+      // The rest of the prize pool goes back to the (only) loser
+      await claim(user2);
+      market = await program.account.market.fetch(marketPda);
+      const prizePool = market.totalPool.toNumber() - market.settleFee.toNumber();
+      assert.equal(market.totalClaimed.toNumber(), prizePool);
+
+      console.log("✅ Payout capped, overflow refunded to the losing side");
+    });
+  });
+
+  describe("27. Daily Stake Limit", () => {
+    const stake = 0.1 * LAMPORTS_PER_SOL;
+    const bettor = Keypair.generate();
+    const userLimitsPda = PublicKey.findProgramAddressSync(
+      [Buffer.from("limits"), bettor.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+    before(async () => {
+      await airdrop(bettor.publicKey, 2);
+      await updateConfig(newAdmin, {
+        enforceUserLimits: true,
+        maxDailyStake: new anchor.BN(2 * stake),
+      });
+    });
+
+    after(async () => {
+      await updateConfig(newAdmin, {
+        enforceUserLimits: false,
+        maxDailyStake: new anchor.BN(0),
+      });
+    });
+
+    const limitedBet = (marketId: anchor.BN) =>
+      program.methods
+        .placeBet(marketId, 0)
+        .accountsPartial({
+          market: getMarketPda(marketId),
+          userBet: getUserBetPda(marketId, bettor.publicKey),
+          config: configPda,
+          user: bettor.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+          userLimits: userLimitsPda,
+        })
+        .signers([bettor])
+        .rpc();
+
+    it("should reject stakes above the daily limit", async () => {
+      const { marketId } = await createMarket(["A", "B"], stake);
+      await limitedBet(marketId);
+      await limitedBet(marketId);
+
+      const limits = await program.account.userLimits.fetch(userLimitsPda);
+      assert.equal(limits.stakedToday.toNumber(), 2 * stake);
+      assert.equal(limits.dayStart.toNumber() % 86400, 0);
+
+      try {
+        await limitedBet(marketId);
+        assert.fail("Should have rejected a stake over the daily limit");
+      } catch (e: any) {
+        assert.include(e.message, "DailyLimitExceeded");
+      }
+
+      console.log("✅ Daily stake limit enforced");
+    });
+  });
+
+  describe("28. Self Exclusion", () => {
+    const bettor = Keypair.generate();
+    const exclusionPda = PublicKey.findProgramAddressSync(
+      [Buffer.from("exclusion"), bettor.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+    const selfExclude = (seconds: number) =>
+      program.methods
+        .selfExclude(new anchor.BN(seconds))
+        .accountsPartial({
+          selfExclusion: exclusionPda,
+          user: bettor.publicKey,
+        })
+        .signers([bettor])
+        .rpc();
+
+    before(async () => {
+      await airdrop(bettor.publicKey, 2);
+    });
+
+    it("should block an excluded user until the exclusion expires", async () => {
+      const { marketId } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await selfExclude(3);
+
+      try {
+        await placeBet(bettor, marketId, 0);
+        assert.fail("Should have rejected an excluded bettor");
+      } catch (e: any) {
+        assert.include(e.message, "SelfExcluded");
+      }
+
+      try {
+        await selfExclude(0);
+        assert.fail("Should not allow shortening the exclusion");
+      } catch (e: any) {
+        assert.include(e.message, "ExclusionCannotBeShortened");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+      await placeBet(bettor, marketId, 0);
+
+      const userBet = await program.account.userBet.fetch(getUserBetPda(marketId, bettor.publicKey));
+      assert.equal(userBet.betCount, 1);
+
+      console.log("✅ Excluded bettor blocked, then allowed after expiry");
+    });
+
+    it("should block an excluded user from switching an existing bet", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(bettor, marketId, 0);
+      await selfExclude(3);
+
+      try {
+        await program.methods
+          .switchOption(marketId, 1)
+          .accountsPartial({
+            market: marketPda,
+            userBet: getUserBetPda(marketId, bettor.publicKey),
+            config: configPda,
+            user: bettor.publicKey,
+            feeVault: feeVault.publicKey,
+            treasury: treasury.publicKey,
+          })
+          .signers([bettor])
+          .rpc();
+        assert.fail("Should have rejected an excluded bettor");
+      } catch (e: any) {
+        assert.include(e.message, "SelfExcluded");
+      }
+
+      const userBet = await program.account.userBet.fetch(getUserBetPda(marketId, bettor.publicKey));
+      assert.equal(userBet.optionIndex, 0);
+
+      console.log("✅ Excluded bettor can't re-position a bet");
+    });
+  });
+
+  describe("29. Account Versions", () => {
+    it("should stamp new accounts with the current version", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 0);
+
+      const config = await program.account.config.fetch(configPda);
+      const market = await program.account.market.fetch(marketPda);
+      const userBet = await program.account.userBet.fetch(getUserBetPda(marketId, user1.publicKey));
+      assert.equal(config.version, 1);
+      assert.equal(market.version, 12);
+      assert.equal(userBet.version, 2);
+
+      console.log("✅ Accounts carry their current versions");
+    });
+
+    it("should reject migrating a market that is already current", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+
+      try {
+        await program.methods
+          .migrateMarket(marketId)
+          .accountsPartial({
+            market: marketPda,
+            config: configPda,
+            admin: newAdmin.publicKey,
+          })
+          .signers([newAdmin])
+          .rpc();
+        assert.fail("Should have rejected an up-to-date market");
+      } catch (e: any) {
+        assert.include(e.message, "AccountUpToDate");
+      }
+
+      console.log("✅ Up-to-date market not migrated");
+    });
+
+    it("should reject migrating a config or bet that is already current", async () => {
+      const { marketId } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 0);
+      const config = await program.account.config.fetch(configPda);
+
+      try {
+        await program.methods
+          .migrateConfig({ ...initialConfigParams, feeVault: config.feeVault, treasury: config.treasury })
+          .accountsPartial({
+            config: configPda,
+            admin: newAdmin.publicKey,
+          })
+          .signers([newAdmin])
+          .rpc();
+        assert.fail("Should have rejected an up-to-date config");
+      } catch (e: any) {
+        assert.include(e.message, "AccountUpToDate");
+      }
+
+      try {
+        await program.methods
+          .migrateUserBet(marketId, user1.publicKey)
+          .accountsPartial({
+            userBet: getUserBetPda(marketId, user1.publicKey),
+            config: configPda,
+            admin: newAdmin.publicKey,
+          })
+          .signers([newAdmin])
+          .rpc();
+        assert.fail("Should have rejected an up-to-date bet");
+      } catch (e: any) {
+        assert.include(e.message, "AccountUpToDate");
+      }
+
+      console.log("✅ Up-to-date config and bet not migrated");
+    });
+
+    it("should reject migrating the config from a non-admin", async () => {
+      const config = await program.account.config.fetch(configPda);
+
+      try {
+        await program.methods
+          .migrateConfig({ ...initialConfigParams, feeVault: config.feeVault, treasury: config.treasury })
+          .accountsPartial({
+            config: configPda,
+            admin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        assert.fail("Should have rejected a non-admin");
+      } catch (e: any) {
+        assert.include(e.message, "NotAdmin");
+      }
+
+      console.log("✅ Non-admin can't migrate the config");
+    });
+  });
+
+  describe("30. Claim Snapshot", () => {
+    it("should let claims exhaust the settlement snapshot exactly", async () => {
+      const stake = 0.1 * LAMPORTS_PER_SOL;
+      const { marketId, marketPda } = await createMarket(["A", "B"], stake);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 0);
+      await placeBet(user3, marketId, 1);
+      await settleMarket(user1, marketId, 0);
+
+      let market = await program.account.market.fetch(marketPda);
+      const prizePool = market.totalPool.toNumber() - market.settleFee.toNumber();
+      assert.equal(market.distributable.toNumber(), prizePool);
+
+      for (const winner of [user1, user2]) {
+        await program.methods
+          .claimPrize(marketId)
+          .accountsPartial({
+            market: marketPda,
+            userBet: getUserBetPda(marketId, winner.publicKey),
+            config: configPda,
+            user: winner.publicKey,
+            feeVault: feeVault.publicKey,
+            treasury: treasury.publicKey,
+          })
+          .signers([winner])
+          .rpc();
+      }
+
+      // Two equal winners split an even prize pool with no dust
+      market = await program.account.market.fetch(marketPda);
+      assert.equal(market.distributable.toNumber(), 0);
+      assert.equal(market.totalClaimed.toNumber(), prizePool);
+
+      console.log("✅ Claims drew the snapshot down to zero");
+    });
+  });
+
+  describe("31. Treasury Split", () => {
+    before(async () => {
+      await updateConfig(newAdmin, { treasurySplitBps: 2000 }); // 20% to the treasury
+    });
+
+    after(async () => {
+      await updateConfig(newAdmin, { treasurySplitBps: 0 });
+    });
+
+    it("should split the create fee between the fee vault and the treasury", async () => {
+      const config = await program.account.config.fetch(configPda);
+      const createFee = config.createFee.toNumber();
+      const vaultBefore = await provider.connection.getBalance(feeVault.publicKey);
+      const treasuryBefore = await provider.connection.getBalance(treasury.publicKey);
+
+      await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+
+      const vaultGain = (await provider.connection.getBalance(feeVault.publicKey)) - vaultBefore;
+      const treasuryGain = (await provider.connection.getBalance(treasury.publicKey)) - treasuryBefore;
+      assert.equal(treasuryGain, Math.floor((createFee * 2000) / 10000));
+      assert.equal(vaultGain, createFee - treasuryGain);
+
+      console.log("✅ Create fee split 80/20");
+    });
+
+    it("should split the join fee between the fee vault and the treasury", async () => {
+      const stake = 0.1 * LAMPORTS_PER_SOL;
+      const { marketId } = await createMarket(["A", "B"], stake);
+      const config = await program.account.config.fetch(configPda);
+      const joinFee = Math.floor((stake * config.joinFeeBps) / 10000);
+      const vaultBefore = await provider.connection.getBalance(feeVault.publicKey);
+      const treasuryBefore = await provider.connection.getBalance(treasury.publicKey);
+
+      await placeBet(user1, marketId, 0);
+
+      const vaultGain = (await provider.connection.getBalance(feeVault.publicKey)) - vaultBefore;
+      const treasuryGain = (await provider.connection.getBalance(treasury.publicKey)) - treasuryBefore;
+      assert.equal(treasuryGain, Math.floor((joinFee * 2000) / 10000));
+      assert.equal(vaultGain, joinFee - treasuryGain);
+
+      console.log("✅ Join fee split 80/20");
+    });
+
+    it("should refund only the fee vault's share of the create fee on cancel", async () => {
+      await updateConfig(newAdmin, { refundCreateFeeOnCancel: true });
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      const config = await program.account.config.fetch(configPda);
+      const createFee = config.createFee.toNumber();
+      const vaultShare = createFee - Math.floor((createFee * 2000) / 10000);
+
+      let market = await program.account.market.fetch(marketPda);
+      assert.equal(market.createFeeToVault.toNumber(), vaultShare);
+
+      const creatorBefore = await provider.connection.getBalance(creator.publicKey);
+      const treasuryBefore = await provider.connection.getBalance(treasury.publicKey);
+      await program.methods
+        .adminCancelMarket(marketId)
+        .accountsPartial({
+          market: marketPda,
+          creatorStats: getCreatorStatsPda(creator.publicKey),
+          config: configPda,
+          admin: newAdmin.publicKey,
+          feeVault: feeVault.publicKey,
+          creator: creator.publicKey,
+        })
+        .signers([newAdmin, feeVault])
+        .rpc();
+      await updateConfig(newAdmin, { refundCreateFeeOnCancel: false });
+
+      const creatorGain = (await provider.connection.getBalance(creator.publicKey)) - creatorBefore;
+      assert.equal(creatorGain, vaultShare);
+      assert.equal(await provider.connection.getBalance(treasury.publicKey), treasuryBefore);
+
+      console.log("✅ Only the vault's 80% refunded");
+    });
+
+    it("should reject a split above 100%", async () => {
+      try {
+        await updateConfig(newAdmin, { treasurySplitBps: 10001 });
+        assert.fail("Should have rejected treasury_split_bps > 10000");
+      } catch (e: any) {
+        assert.include(e.message, "InvalidFeeBps");
+      }
+    });
+  });
+
+  describe("32. Switch Option", () => {
+    it("should move a bet and its participant to the new option", async () => {
+      const stake = 0.1 * LAMPORTS_PER_SOL;
+      const { marketId, marketPda } = await createMarket(["A", "B"], stake);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+
+      const vaultBefore = await provider.connection.getBalance(feeVault.publicKey);
+
+      const switchOption = (optionIndex: number) =>
+        program.methods
+          .switchOption(marketId, optionIndex)
+          .accountsPartial({
+            market: marketPda,
+            userBet: getUserBetPda(marketId, user1.publicKey),
+            config: configPda,
+            user: user1.publicKey,
+            feeVault: feeVault.publicKey,
+            treasury: treasury.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+
+      await switchOption(1);
+
+      const market = await program.account.market.fetch(marketPda);
+      assert.equal(market.optionTotals[0].toNumber(), 0);
+      assert.equal(market.optionTotals[1].toNumber(), 2 * stake);
+      assert.equal(market.optionParticipants[0], 0);
+      assert.equal(market.optionParticipants[1], 2);
+      assert.equal(market.totalPool.toNumber(), 2 * stake);
+
+      const userBet = await program.account.userBet.fetch(getUserBetPda(marketId, user1.publicKey));
+      assert.equal(userBet.optionIndex, 1);
+
+      const vaultGain = (await provider.connection.getBalance(feeVault.publicKey)) - vaultBefore;
+      assert.equal(vaultGain, (stake * 100) / 10000);
+
+      try {
+        await switchOption(1);
+        assert.fail("Should have rejected switching to the same option");
+      } catch (e: any) {
+        assert.include(e.message, "SameOption");
+      }
+
+      console.log("✅ Bet switched, totals and participant counts consistent");
+    });
+  });
+
+  describe("33. Default Pubkey Guards", () => {
+    it("should reject a default fee vault in update_config", async () => {
+      try {
+        await updateConfig(newAdmin, { feeVault: PublicKey.default });
+        assert.fail("Should have rejected a default fee vault");
+      } catch (e: any) {
+        assert.include(e.message, "InvalidFeeVault");
+      }
+    });
+
+    it("should reject a default treasury in update_config", async () => {
+      try {
+        await updateConfig(newAdmin, { treasury: PublicKey.default });
+        assert.fail("Should have rejected a default treasury");
+      } catch (e: any) {
+        assert.include(e.message, "InvalidTreasury");
+      }
+    });
+
+    it("should reject the config PDA as fee vault", async () => {
+      try {
+        await updateConfig(newAdmin, { feeVault: configPda });
+        assert.fail("Should have rejected the config PDA as fee vault");
+      } catch (e: any) {
+        assert.include(e.message, "InvalidFeeVault");
+      }
+    });
+
+    it("should reject a default fee vault in set_fee_vault", async () => {
+      try {
+        await program.methods
+          .setFeeVault(PublicKey.default)
+          .accountsPartial({
+            config: configPda,
+            admin: newAdmin.publicKey,
+          })
+          .signers([newAdmin])
+          .rpc();
+        assert.fail("Should have rejected a default fee vault");
+      } catch (e: any) {
+        assert.include(e.message, "InvalidFeeVault");
+      }
+
+      const config = await program.account.config.fetch(configPda);
+      assert.isTrue(config.feeVault.equals(feeVault.publicKey));
+    });
+  });
+
+  describe("34. Blacklist", () => {
+    const blacklistPda = PublicKey.findProgramAddressSync(
+      [Buffer.from("blacklist"), user1.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+    before(async () => {
+      await updateConfig(newAdmin, { blacklistEnabled: true });
+    });
+
+    after(async () => {
+      await updateConfig(newAdmin, { blacklistEnabled: false });
+    });
+
+    it("should block new bets but still allow claiming a prior win", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+
+      await program.methods
+        .blacklistUser(user1.publicKey)
+        .accountsPartial({
+          config: configPda,
+          blacklistEntry: blacklistPda,
+          admin: newAdmin.publicKey,
+        })
+        .signers([newAdmin])
+        .rpc();
+
+      const { marketId: otherId } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      try {
+        await placeBet(user1, otherId, 0);
+        assert.fail("Should have rejected a blacklisted bettor");
+      } catch (e: any) {
+        assert.include(e.message, "UserBlacklisted");
+      }
+
+      await settleMarket(user2, marketId, 0);
+      await program.methods
+        .claimPrize(marketId)
+        .accountsPartial({
+          market: marketPda,
+          userBet: getUserBetPda(marketId, user1.publicKey),
+          config: configPda,
+          user: user1.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+        })
+        .signers([user1])
+        .rpc();
+
+      const userBet = await program.account.userBet.fetch(getUserBetPda(marketId, user1.publicKey));
+      assert.isTrue(userBet.claimed);
+
+      await program.methods
+        .unblacklistUser(user1.publicKey)
+        .accountsPartial({
+          config: configPda,
+          blacklistEntry: blacklistPda,
+          admin: newAdmin.publicKey,
+        })
+        .signers([newAdmin])
+        .rpc();
+      await placeBet(user1, otherId, 0);
+
+      console.log("✅ Blacklisted user blocked from betting but could still claim");
+    });
+  });
+
+  describe("35. Creator Market Limit", () => {
+    const spammer = Keypair.generate();
+    const idler = Keypair.generate();
+
+    before(async () => {
+      await airdrop(spammer.publicKey, 2);
+      await airdrop(idler.publicKey, 2);
+      await updateConfig(newAdmin, { maxMarketsPerCreator: 2 });
+    });
+
+    after(async () => {
+      await updateConfig(newAdmin, { maxMarketsPerCreator: 0 });
+    });
+
+    const createSpamMarket = async (owner = spammer, betDeadlineIn = 3600, resolveIn = 7200) => {
+      const config = await program.account.config.fetch(configPda);
+      const marketId = config.nextMarketId;
+      const now = Math.floor(Date.now() / 1000);
+
+      await program.methods
+        .createMarket(
+          `Spam ${marketId.toString()}`,
+          ["Yes", "No"],
+          new anchor.BN(0.01 * LAMPORTS_PER_SOL),
+          new anchor.BN(now + betDeadlineIn),
+          new anchor.BN(now + resolveIn),
+          marketParams(2)
+        )
+        .accountsPartial({
+          market: getMarketPda(marketId),
+          config: configPda,
+          creator: owner.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+          creatorStats: getCreatorStatsPda(owner.publicKey),
+        })
+        .signers([owner])
+        .rpc();
+
+      return marketId;
+    };
+
+    it("should reject markets past the limit until one is closed out", async () => {
+      const firstId = await createSpamMarket();
+      await createSpamMarket();
+
+      try {
+        await createSpamMarket();
+        assert.fail("Should have rejected a third active market");
+      } catch (e: any) {
+        assert.include(e.message, "TooManyActiveMarkets");
+      }
+
+      await program.methods
+        .adminCancelMarket(firstId)
+        .accountsPartial({
+          market: getMarketPda(firstId),
+          creatorStats: getCreatorStatsPda(spammer.publicKey),
+          config: configPda,
+          admin: newAdmin.publicKey,
+        })
+        .signers([newAdmin])
+        .rpc();
+
+      let stats = await program.account.creatorStats.fetch(getCreatorStatsPda(spammer.publicKey));
+      assert.equal(stats.activeMarkets, 1);
+
+      await createSpamMarket();
+      stats = await program.account.creatorStats.fetch(getCreatorStatsPda(spammer.publicKey));
+      assert.equal(stats.activeMarkets, 2);
+
+      console.log("✅ Creator limit enforced and freed on cancel");
+    });
+
+    const closeEmptyMarket = async (marketId: anchor.BN, owner: PublicKey) => {
+      await program.methods
+        .closeEmptyMarket(marketId)
+        .accountsPartial({
+          market: getMarketPda(marketId),
+          creatorStats: getCreatorStatsPda(owner),
+          creator: owner,
+          caller: user1.publicKey,
+        })
+        .signers([user1])
+        .rpc();
+    };
+
+    it("should let anyone close an empty market after resolve time, freeing the slot", async () => {
+      const emptyId = await createSpamMarket(idler, 2, 3);
+      const liveId = await createSpamMarket(idler);
+
+      try {
+        await closeEmptyMarket(liveId, idler.publicKey);
+        assert.fail("Should have rejected closing before resolve time");
+      } catch (e: any) {
+        assert.include(e.message, "ResolveTimeNotReached");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+
+      const rent = (await provider.connection.getAccountInfo(getMarketPda(emptyId)))!.lamports;
+      const idlerBefore = await provider.connection.getBalance(idler.publicKey);
+      await closeEmptyMarket(emptyId, idler.publicKey);
+
+      assert.isNull(await provider.connection.getAccountInfo(getMarketPda(emptyId)));
+      assert.equal((await provider.connection.getBalance(idler.publicKey)) - idlerBefore, rent);
+      const stats = await program.account.creatorStats.fetch(getCreatorStatsPda(idler.publicKey));
+      assert.equal(stats.activeMarkets, 1);
+
+      // The freed slot is usable again
+      await createSpamMarket(idler);
+
+      console.log("✅ Empty market closed and creator slot released");
+    });
+
+    it("should refuse to close a market that has bets", async () => {
+      const { marketId } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 0);
+
+      try {
+        await closeEmptyMarket(marketId, creator.publicKey);
+        assert.fail("Should have rejected a market with bets");
+      } catch (e: any) {
+        assert.include(e.message, "MarketNotEmpty");
+      }
+    });
+  });
+
+  describe("36. Outcome Stats", () => {
+    it("should count settlements by winning option", async () => {
+      const stake = 0.1 * LAMPORTS_PER_SOL;
+      const before = await program.account.outcomeStats.fetch(getOutcomeStatsPda());
+
+      const { marketId: firstId } = await createMarket(["A", "B"], stake);
+      await placeBet(user1, firstId, 0);
+      await placeBet(user2, firstId, 1);
+      await settleMarket(user1, firstId, 0);
+
+      const { marketId: secondId } = await createMarket(["A", "B"], stake);
+      await placeBet(user1, secondId, 0);
+      await placeBet(user2, secondId, 1);
+      await settleMarket(user2, secondId, 1);
+
+      const after = await program.account.outcomeStats.fetch(getOutcomeStatsPda());
+      assert.equal(after.settledMarkets.toNumber() - before.settledMarkets.toNumber(), 2);
+      assert.equal(after.outcomeCounts[0].toNumber() - before.outcomeCounts[0].toNumber(), 1);
+      assert.equal(after.outcomeCounts[1].toNumber() - before.outcomeCounts[1].toNumber(), 1);
+      assert.equal(after.settledVolume.toNumber() - before.settledVolume.toNumber(), 4 * stake);
+
+      console.log("✅ Outcome stats updated on settlement");
+    });
+
+    it("should count a market refunded for too few bettors as an uncontested refund", async () => {
+      const before = await program.account.outcomeStats.fetch(getOutcomeStatsPda());
+
+      await updateConfig(newAdmin, { minDistinctParticipants: 2 });
+      const { marketId } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 0);
+      await settleMarket(user1, marketId, 0);
+      await updateConfig(newAdmin, { minDistinctParticipants: 0 });
+
+      const after = await program.account.outcomeStats.fetch(getOutcomeStatsPda());
+      assert.equal(after.uncontestedRefunds.toNumber() - before.uncontestedRefunds.toNumber(), 1);
+      assert.equal(after.settledMarkets.toNumber(), before.settledMarkets.toNumber());
+
+      console.log("✅ Thin market counted as an uncontested refund");
+    });
+
+    it("should only let the admin create the stats account, and only once", async () => {
+      try {
+        await program.methods
+          .initOutcomeStats()
+          .accountsPartial({
+            outcomeStats: getOutcomeStatsPda(),
+            config: configPda,
+            admin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        assert.fail("Should have rejected a non-admin");
+      } catch (e: any) {
+        assert.include(e.message, "NotAdmin");
+      }
+
+      try {
+        await program.methods
+          .initOutcomeStats()
+          .accountsPartial({
+            outcomeStats: getOutcomeStatsPda(),
+            config: configPda,
+            admin: newAdmin.publicKey,
+          })
+          .signers([newAdmin])
+          .rpc();
+        assert.fail("Should have rejected an existing stats account");
+      } catch (e: any) {
+        // Reported by the system program, so it shows up in the logs rather than as an error code
+        assert.include((e.logs ?? []).join("\n") + e.message, "already in use");
+      }
+
+      console.log("✅ OutcomeStats init is admin-only and one-shot");
+    });
+  });
+
+  describe("37. Late Bet", () => {
+    it("should reject a bet after the deadline without moving any lamports", async () => {
+      const config = await program.account.config.fetch(configPda);
+      const marketId = config.nextMarketId;
+      const marketPda = getMarketPda(marketId);
+      const now = Math.floor(Date.now() / 1000);
+
+      await program.methods
+        .createMarket(
+          "Closing soon",
+          ["Yes", "No"],
+          new anchor.BN(0.1 * LAMPORTS_PER_SOL),
+          new anchor.BN(now + 2),
+          new anchor.BN(now + 3),
+          marketParams(1)
+        )
+        .accountsPartial({
+          market: marketPda,
+          config: configPda,
+          creator: creator.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+        })
+        .signers([creator])
+        .rpc();
+
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+
+      const userBefore = await provider.connection.getBalance(user1.publicKey);
+      const vaultBefore = await provider.connection.getBalance(feeVault.publicKey);
+      const marketBefore = await provider.connection.getBalance(marketPda);
+
+      try {
+        await placeBet(user1, marketId, 0);
+        assert.fail("Should have rejected a late bet");
+      } catch (e: any) {
+        assert.include(e.message, "BettingClosed");
+      }
+
+      assert.equal(await provider.connection.getBalance(user1.publicKey), userBefore);
+      assert.equal(await provider.connection.getBalance(feeVault.publicKey), vaultBefore);
+      assert.equal(await provider.connection.getBalance(marketPda), marketBefore);
+      assert.isNull(await provider.connection.getAccountInfo(getUserBetPda(marketId, user1.publicKey)));
+
+      console.log("✅ Late bet rejected with no lamports moved");
+    });
+  });
+
+  describe("38. Cancelled Bet Cleanup", () => {
+    it("should refund and close a UserBet left on a cancelled market", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 0);
+
+      await program.methods
+        .adminCancelMarket(marketId)
+        .accountsPartial({
+          market: marketPda,
+          creatorStats: getCreatorStatsPda(creator.publicKey),
+          config: configPda,
+          admin: newAdmin.publicKey,
+        })
+        .signers([newAdmin])
+        .rpc();
+
+      const userBetPda = getUserBetPda(marketId, user1.publicKey);
+      const stake = (await program.account.userBet.fetch(userBetPda)).amount.toNumber();
+      const rent = (await provider.connection.getAccountInfo(userBetPda))!.lamports;
+      const balanceBefore = await provider.connection.getBalance(user1.publicKey);
+
+      await program.methods
+        .closeCancelledBet(marketId)
+        .accountsPartial({
+          market: marketPda,
+          userBet: userBetPda,
+          config: configPda,
+          admin: newAdmin.publicKey,
+          user: user1.publicKey,
+        })
+        .signers([newAdmin])
+        .rpc();
+
+      const balanceAfter = await provider.connection.getBalance(user1.publicKey);
+      assert.equal(balanceAfter - balanceBefore, stake + rent);
+      assert.isNull(await provider.connection.getAccountInfo(userBetPda));
+
+      console.log("✅ Orphaned UserBet refunded and closed");
+    });
+
+    it("should refuse to close a bet on a live market", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 0);
+
+      try {
+        await program.methods
+          .closeCancelledBet(marketId)
+          .accountsPartial({
+            market: marketPda,
+            userBet: getUserBetPda(marketId, user1.publicKey),
+            config: configPda,
+            admin: newAdmin.publicKey,
+            user: user1.publicKey,
+          })
+          .signers([newAdmin])
+          .rpc();
+        assert.fail("Should have rejected a live market");
+      } catch (e: any) {
+        assert.include(e.message, "MarketNotCancelled");
+      }
+    });
+  });
+
+  describe("39. Cancellation Fee Credit", () => {
+    const credit = 200000;
+    const feeCreditPda = PublicKey.findProgramAddressSync(
+      [Buffer.from("fee_credit"), user2.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+    before(async () => {
+      await updateConfig(newAdmin, { cancelFeeCredit: new anchor.BN(credit) });
+    });
+
+    after(async () => {
+      await updateConfig(newAdmin, { cancelFeeCredit: new anchor.BN(0) });
+    });
+
+    it("should credit a refunded bettor and spend it on their next join fee", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user2, marketId, 0);
+
+      await program.methods
+        .adminCancelMarket(marketId)
+        .accountsPartial({
+          market: marketPda,
+          creatorStats: getCreatorStatsPda(creator.publicKey),
+          config: configPda,
+          admin: newAdmin.publicKey,
+        })
+        .signers([newAdmin])
+        .rpc();
+
+      await program.methods
+        .claimCancelledRefund(marketId)
+        .accountsPartial({
+          market: marketPda,
+          userBet: getUserBetPda(marketId, user2.publicKey),
+          config: configPda,
+          user: user2.publicKey,
+          feeCredit: feeCreditPda,
+        })
+        .signers([user2])
+        .rpc();
+
+      let feeCredit = await program.account.feeCredit.fetch(feeCreditPda);
+      assert.equal(feeCredit.balance.toNumber(), credit);
+
+      const { marketId: nextId } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      const joinFee = (0.1 * LAMPORTS_PER_SOL * initialConfigParams.joinFeeBps) / 10000;
+      const vaultBefore = await provider.connection.getBalance(feeVault.publicKey);
+
+      await program.methods
+        .placeBet(nextId, 0)
+        .accountsPartial({
+          market: getMarketPda(nextId),
+          userBet: getUserBetPda(nextId, user2.publicKey),
+          config: configPda,
+          user: user2.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+          feeCredit: feeCreditPda,
+        })
+        .signers([user2])
+        .rpc();
+
+      const vaultAfter = await provider.connection.getBalance(feeVault.publicKey);
+      assert.equal(vaultAfter - vaultBefore, joinFee - credit);
+      feeCredit = await program.account.feeCredit.fetch(feeCreditPda);
+      assert.equal(feeCredit.balance.toNumber(), 0);
+
+      console.log("✅ Fee credit granted on cancel and redeemed on the next bet");
+    });
+
+    const user3FeeCreditPda = PublicKey.findProgramAddressSync(
+      [Buffer.from("fee_credit"), user3.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+    const claimRefundWithCredit = async (marketId: anchor.BN) => {
+      await program.methods
+        .claimCancelledRefund(marketId)
+        .accountsPartial({
+          market: getMarketPda(marketId),
+          userBet: getUserBetPda(marketId, user3.publicKey),
+          config: configPda,
+          user: user3.publicKey,
+          feeCredit: user3FeeCreditPda,
+        })
+        .signers([user3])
+        .rpc();
+    };
+
+    const adminCancel = async (marketId: anchor.BN) => {
+      await program.methods
+        .adminCancelMarket(marketId)
+        .accountsPartial({
+          market: getMarketPda(marketId),
+          creatorStats: getCreatorStatsPda(creator.publicKey),
+          config: configPda,
+          admin: newAdmin.publicKey,
+        })
+        .signers([newAdmin])
+        .rpc();
+    };
+
+    it("should grant no credit on an automatically cancelled market", async () => {
+      await updateConfig(newAdmin, { minDistinctParticipants: 2 });
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user3, marketId, 0);
+      await settleMarket(user3, marketId, 0);
+      await updateConfig(newAdmin, { minDistinctParticipants: 0 });
+
+      const market = await program.account.market.fetch(marketPda);
+      assert.equal(market.status, 5); // CANCELLED
+      assert.isFalse(market.cancelledByAdmin);
+
+      await claimRefundWithCredit(marketId);
+
+      const feeCredit = await program.account.feeCredit.fetch(user3FeeCreditPda);
+      assert.equal(feeCredit.balance.toNumber(), 0);
+
+      console.log("✅ Auto-cancel refunded without credit");
+    });
+
+    it("should cap the credit at the join fee the bet paid", async () => {
+      const stake = 0.1 * LAMPORTS_PER_SOL;
+      const joinFee = (stake * initialConfigParams.joinFeeBps) / 10000;
+      await updateConfig(newAdmin, { cancelFeeCredit: new anchor.BN(joinFee * 10) });
+
+      const { marketId } = await createMarket(["A", "B"], stake);
+      await placeBet(user3, marketId, 0);
+      assert.equal(
+        (await program.account.userBet.fetch(getUserBetPda(marketId, user3.publicKey))).joinFeePaid.toNumber(),
+        joinFee
+      );
+      await adminCancel(marketId);
+      await claimRefundWithCredit(marketId);
+
+      const feeCredit = await program.account.feeCredit.fetch(user3FeeCreditPda);
+      assert.equal(feeCredit.balance.toNumber(), joinFee);
+
+      console.log("✅ Credit capped at the join fee");
+    });
+
+    it("should grant the same credit when the admin closes an unclaimed bet", async () => {
+      const stake = 0.1 * LAMPORTS_PER_SOL;
+      const joinFee = (stake * initialConfigParams.joinFeeBps) / 10000;
+      const before = (await program.account.feeCredit.fetch(user3FeeCreditPda)).balance.toNumber();
+
+      const { marketId, marketPda } = await createMarket(["A", "B"], stake);
+      await placeBet(user3, marketId, 0);
+      await adminCancel(marketId);
+
+      await program.methods
+        .closeCancelledBet(marketId)
+        .accountsPartial({
+          market: marketPda,
+          userBet: getUserBetPda(marketId, user3.publicKey),
+          config: configPda,
+          admin: newAdmin.publicKey,
+          user: user3.publicKey,
+          feeCredit: user3FeeCreditPda,
+        })
+        .signers([newAdmin])
+        .rpc();
+
+      const feeCredit = await program.account.feeCredit.fetch(user3FeeCreditPda);
+      // The credit is still set above the join fee, so the cap applies here too
+      assert.equal(feeCredit.balance.toNumber() - before, joinFee);
+
+      console.log("✅ Closing an unclaimed bet grants the credit");
+    });
+  });
+
+  describe("40. Check Winner", () => {
+    it("should report winning and losing bets via return data", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+
+      const checkWinner = (user: Keypair) =>
+        program.methods
+          .checkWinner(marketId)
+          .accountsPartial({ market: marketPda, userBet: getUserBetPda(marketId, user.publicKey) })
+          .view();
+
+      try {
+        await checkWinner(user1);
+        assert.fail("Should have rejected an unsettled market");
+      } catch (e: any) {
+        assert.include(e.message, "MarketNotSettled");
+      }
+
+      await settleMarket(user1, marketId, 0);
+
+      assert.isTrue(await checkWinner(user1));
+      assert.isFalse(await checkWinner(user2));
+
+      console.log("✅ Winner check decoded from return data");
+    });
+  });
+
+  describe("41. Auto-Lock", () => {
+    it("should lock an option once it outweighs the rest by the ratio", async () => {
+      const { marketId } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL, { autoLockRatio: 2 });
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+      await placeBet(user3, marketId, 0);
+      await placeBet(creator, marketId, 0); // A: 0.3 SOL against 0.1 SOL, now over 2x
+
+      try {
+        await placeBet(user1, marketId, 0);
+        assert.fail("Should have rejected a bet on the locked option");
+      } catch (e: any) {
+        assert.include(e.message, "AutoLocked");
+      }
+
+      // The light side still takes bets
+      await placeBet(user2, marketId, 1);
+
+      const market = await program.account.market.fetch(getMarketPda(marketId));
+      assert.equal(market.optionTotals[0].toNumber(), 0.3 * LAMPORTS_PER_SOL);
+      assert.equal(market.optionTotals[1].toNumber(), 0.2 * LAMPORTS_PER_SOL);
+
+      console.log("✅ Heavy option locked, light option still open");
+    });
+  });
+
+  describe("42. Minimum Participants", () => {
+    before(async () => {
+      await updateConfig(newAdmin, { minDistinctParticipants: 2 });
+    });
+
+    after(async () => {
+      await updateConfig(newAdmin, { minDistinctParticipants: 0 });
+    });
+
+    it("should settle a market with enough bettors and refund one without", async () => {
+      const { marketId: fullId, marketPda: fullPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, fullId, 0);
+      await placeBet(user2, fullId, 0);
+      await settleMarket(user1, fullId, 0);
+
+      const { marketId: thinId, marketPda: thinPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, thinId, 0);
+      await placeBet(user1, thinId, 0); // a second bet is not a second participant
+      await settleMarket(user1, thinId, 0);
+
+      assert.equal((await program.account.market.fetch(fullPda)).status, 4); // SETTLED
+      assert.equal((await program.account.market.fetch(thinPda)).status, 5); // CANCELLED
+
+      console.log("✅ Thin market refunded, two-bettor market settled");
+    });
+  });
+
+  describe("43. Market Freeze", () => {
+    it("should block bets on a frozen market but still settle and pay out", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+
+      await program.methods
+        .freezeMarket(marketId)
+        .accountsPartial({ market: marketPda, config: configPda, admin: newAdmin.publicKey })
+        .signers([newAdmin])
+        .rpc();
+
+      try {
+        await placeBet(user3, marketId, 0);
+        assert.fail("Should have rejected a bet on a frozen market");
+      } catch (e: any) {
+        assert.include(e.message, "MarketFrozen");
+      }
+
+      await settleMarket(user1, marketId, 0);
+
+      const balanceBefore = await provider.connection.getBalance(user1.publicKey);
+      await program.methods
+        .claimPrize(marketId)
+        .accountsPartial({
+          market: marketPda,
+          userBet: getUserBetPda(marketId, user1.publicKey),
+          config: configPda,
+          user: user1.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+        })
+        .signers([user1])
+        .rpc();
+      const balanceAfter = await provider.connection.getBalance(user1.publicKey);
+      assert.isTrue(balanceAfter > balanceBefore);
+
+      console.log("✅ Frozen market blocked bets but settled and paid out");
+    });
+
+    it("should take bets again once unfrozen", async () => {
+      const { marketId, marketPda } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+
+      await program.methods
+        .freezeMarket(marketId)
+        .accountsPartial({ market: marketPda, config: configPda, admin: newAdmin.publicKey })
+        .signers([newAdmin])
+        .rpc();
+      await program.methods
+        .unfreezeMarket(marketId)
+        .accountsPartial({ market: marketPda, config: configPda, admin: newAdmin.publicKey })
+        .signers([newAdmin])
+        .rpc();
+      await placeBet(user1, marketId, 0);
+
+      const market = await program.account.market.fetch(marketPda);
+      assert.isFalse(market.frozen);
+      assert.equal(market.optionTotals[0].toNumber(), 0.1 * LAMPORTS_PER_SOL);
+
+      console.log("✅ Unfrozen market accepts bets");
+    });
+  });
+
+  describe("44. Refund Accounting", () => {
+    it("should record the total refunded from a cancelled market", async () => {
+      const stake = 0.1 * LAMPORTS_PER_SOL;
+      const { marketId, marketPda } = await createMarket(["A", "B"], stake);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+
+      await program.methods
+        .adminCancelMarket(marketId)
+        .accountsPartial({
+          market: marketPda,
+          creatorStats: getCreatorStatsPda(creator.publicKey),
+          config: configPda,
+          admin: newAdmin.publicKey,
+        })
+        .signers([newAdmin])
+        .rpc();
+
+      for (const user of [user1, user2]) {
+        await program.methods
+          .claimCancelledRefund(marketId)
+          .accountsPartial({
+            market: marketPda,
+            userBet: getUserBetPda(marketId, user.publicKey),
+            config: configPda,
+            user: user.publicKey,
+          })
+          .signers([user])
+          .rpc();
+      }
+
+      const market = await program.account.market.fetch(marketPda);
+      assert.equal(market.totalRefunded.toNumber(), 3 * stake);
+      assert.equal(market.totalRefunded.toNumber(), market.totalPool.toNumber());
+
+      console.log("✅ Refund total persisted on the market");
+    });
+  });
+
+  describe("45. Claim Cooldown", () => {
+    const userStatsPda = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_stats"), user1.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+    after(async () => {
+      await updateConfig(newAdmin, { claimCooldown: new anchor.BN(0) });
+    });
+
+    it("should reject a second claim inside the cooldown and allow it after", async () => {
+      const marketIds: anchor.BN[] = [];
+      for (let i = 0; i < 2; i++) {
+        const { marketId } = await createMarket(["A", "B"], 0.1 * LAMPORTS_PER_SOL);
+        await placeBet(user1, marketId, 0);
+        await placeBet(user2, marketId, 1);
+        await settleMarket(user1, marketId, 0);
+        marketIds.push(marketId);
+      }
+
+      await updateConfig(newAdmin, { claimCooldown: new anchor.BN(3) });
+
+      const claim = (marketId: anchor.BN) =>
+        program.methods
+          .claimPrize(marketId)
+          .accountsPartial({
+            market: getMarketPda(marketId),
+            userBet: getUserBetPda(marketId, user1.publicKey),
+            config: configPda,
+            user: user1.publicKey,
+            feeVault: feeVault.publicKey,
+            treasury: treasury.publicKey,
+            userStats: userStatsPda,
+          })
+          .signers([user1])
+          .rpc();
+
+      await claim(marketIds[0]);
+
+      try {
+        await claim(marketIds[1]);
+        assert.fail("Should have rejected a claim inside the cooldown");
+      } catch (e: any) {
+        assert.include(e.message, "ClaimTooSoon");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+      await claim(marketIds[1]);
+
+      const userBet = await program.account.userBet.fetch(getUserBetPda(marketIds[1], user1.publicKey));
+      assert.isTrue(userBet.claimed);
+
+      console.log("✅ Claim cooldown enforced");
+    });
+  });
+
+  describe("46. Settle Authority", () => {
+    const resolver = Keypair.generate();
+
+    const createResolvedMarket = async (settleAuthority: PublicKey, betDeadlineIn: number) => {
+      const config = await program.account.config.fetch(configPda);
+      const marketId = config.nextMarketId;
+      const now = Math.floor(Date.now() / 1000);
+
+      await program.methods
+        .createMarket(
+          `Resolved ${marketId.toString()}`,
+          ["Home", "Away"],
+          new anchor.BN(0.1 * LAMPORTS_PER_SOL),
+          new anchor.BN(now + betDeadlineIn),
+          new anchor.BN(now + betDeadlineIn + 1),
+          marketParams(2, { settleAuthority })
+        )
+        .accountsPartial({
+          market: getMarketPda(marketId),
+          config: configPda,
+          creator: creator.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+        })
+        .signers([creator])
+        .rpc();
+
+      return marketId;
+    };
+
+    it("should settle a market through its settle authority only", async () => {
+      const marketId = await createResolvedMarket(resolver.publicKey, 3);
+      await placeBet(user1, marketId, 0);
+      await placeBet(user2, marketId, 1);
+
+      try {
+        await authoritySettle(marketId, resolver, 1);
+        assert.fail("Should have rejected settling while betting is open");
+      } catch (e: any) {
+        assert.include(e.message, "BettingStillOpen");
+      }
+
+      try {
+        await program.methods
+          .initiateSettlement(marketId, 0)
+          .accountsPartial({
+            market: getMarketPda(marketId),
+            userBet: getUserBetPda(marketId, user1.publicKey),
+            proposer: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        assert.fail("Should have rejected a bettor proposal");
+      } catch (e: any) {
+        assert.include(e.message, "SettleAuthorityOnly");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+
+      try {
+        await authoritySettle(marketId, user1, 0);
+        assert.fail("Should have rejected a non-authority");
+      } catch (e: any) {
+        assert.include(e.message, "NotSettleAuthority");
+      }
+
+      await authoritySettle(marketId, resolver, 1);
+
+      const market = await program.account.market.fetch(getMarketPda(marketId));
+      assert.equal(market.status, 4); // SETTLED
+      assert.equal(market.finalOutcome, 1);
+      assert.isTrue(market.settleAuthority.equals(resolver.publicKey));
+
+      console.log("✅ Market settled by its settle authority");
+    });
+
+    it("should reject the default pubkey as settle authority", async () => {
+      try {
+        await createResolvedMarket(PublicKey.default, 3600);
+        assert.fail("Should have rejected a default settle authority");
+      } catch (e: any) {
+        assert.include(e.message, "InvalidSettleAuthority");
+      }
+    });
+  });
+
+  describe("47. Slot-Based Betting Close", () => {
+    const resolver = Keypair.generate();
+
+    const createSlotMarket = async (betDeadline: number, betDeadlineSlot: number) => {
+      const config = await program.account.config.fetch(configPda);
+      const marketId = config.nextMarketId;
+      const now = Math.floor(Date.now() / 1000);
+
+      await program.methods
+        .createMarket(
+          `Slot ${marketId.toString()}`,
+          ["A", "B"],
+          new anchor.BN(0.1 * LAMPORTS_PER_SOL),
+          new anchor.BN(betDeadline),
+          new anchor.BN(now + 3600),
+          marketParams(2, {
+            settleAuthority: resolver.publicKey,
+            betDeadlineSlot: new anchor.BN(betDeadlineSlot),
+          })
+        )
+        .accountsPartial({
+          market: getMarketPda(marketId),
+          config: configPda,
+          creator: creator.publicKey,
+          feeVault: feeVault.publicKey,
+          treasury: treasury.publicKey,
+        })
+        .signers([creator])
+        .rpc();
+
+      return marketId;
+    };
+
+    it("should gate bets and settlement on the deadline slot", async () => {
+      const deadlineSlot = (await provider.connection.getSlot()) + 15;
+      const marketId = await createSlotMarket(0, deadlineSlot);
+
+      const market = await program.account.market.fetch(getMarketPda(marketId));
+      assert.equal(market.betDeadlineSlot.toNumber(), deadlineSlot);
+      assert.equal(market.betDeadline.toNumber(), 0);
+
+      await placeBet(user1, marketId, 0);
+
+      try {
+        await authoritySettle(marketId, resolver, 0);
+        assert.fail("Should have rejected settling before the deadline slot");
+      } catch (e: any) {
+        assert.include(e.message, "BettingStillOpen");
+      }
+
+      while ((await provider.connection.getSlot()) < deadlineSlot) {
+        await new Promise((resolve) => setTimeout(resolve, 400));
+      }
+
+      try {
+        await placeBet(user2, marketId, 1);
+        assert.fail("Should have rejected a bet after the deadline slot");
+      } catch (e: any) {
+        assert.include(e.message, "BettingClosed");
+      }
+
+      await authoritySettle(marketId, resolver, 0);
+      assert.equal((await program.account.market.fetch(getMarketPda(marketId))).status, 4); // SETTLED
+
+      console.log("✅ Slot-based market closed and settled by slot");
+    });
+
+    it("should reject a market with both a deadline timestamp and a deadline slot", async () => {
+      const now = Math.floor(Date.now() / 1000);
+      const slot = await provider.connection.getSlot();
+
+      try {
+        await createSlotMarket(now + 60, slot + 100);
+        assert.fail("Should have rejected two expiry modes");
+      } catch (e: any) {
+        assert.include(e.message, "InvalidBetDeadline");
+      }
+    });
+  });
 });